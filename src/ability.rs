@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat::Attack;
+use crate::combat::AttackOutcome;
+use crate::combat::resolve_attack;
+use crate::ecs::EntityId;
+use crate::entity::Entity;
+use crate::log::Event;
+use crate::log::WHITE;
+use crate::log::YELLOW;
+use crate::rng::Stream;
+use crate::time::DeltaTime;
+use crate::world::World;
+
+/// The keys abilities are bound to, in the order they're known
+pub const ABILITY_KEYS: [&str; 4] = ["Q", "W", "R", "T"];
+/// Half the angle a cone spreads out by, in degrees
+const CONE_HALF_ANGLE: f32 = 30.0;
+
+const AREA_SPRITE: [u32; 2] = [1, 3];
+const AREA_COLOR: u32 = 0x8040_80ff;
+const CURSOR_SPRITE: [u32; 2] = [2, 3];
+
+/// The tiles an ability affects, relative to its caster and the targeted tile
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Shape {
+    /// Just the targeted tile
+    Single,
+    /// Every tile from the caster towards the target until the range runs out or a wall is hit
+    Line,
+    /// A wedge spreading out from the caster towards the target
+    Cone,
+    /// Every tile within a radius of the targeted tile
+    Burst(i32),
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum AbilityKind {
+    PoisonDart,
+    Firebolt,
+    ConeOfCold,
+    Fireball,
+}
+
+/// The rules an ability follows
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct AbilityDefinition {
+    pub name: String,
+    pub shape: Shape,
+    /// The furthest tile that can be targeted, or the length of lines and cones
+    pub range: i32,
+    /// Rolled against everyone caught in the area
+    pub attack: Attack,
+    /// How long before the ability can be used again, in seconds
+    pub cooldown: f32,
+    /// The energy spent using it
+    pub cost: i32,
+}
+
+/// The rules for every ability, read from `data/abilities.json` the first time one is needed
+static DEFINITIONS: OnceLock<BTreeMap<AbilityKind, AbilityDefinition>> = OnceLock::new();
+
+impl AbilityKind {
+    /// Get the rules for a specific ability
+    pub fn definition(self) -> &'static AbilityDefinition {
+        let definitions = DEFINITIONS.get_or_init(|| {
+            serde_json::from_str(include_str!("../data/abilities.json")).expect("ability definitions should be valid")
+        });
+
+        &definitions[&self]
+    }
+}
+
+/// The abilities an entity knows and how long until each can be used again
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Abilities {
+    pub known: Vec<AbilityKind>,
+    /// Seconds left on each ability that's cooling down
+    pub cooldowns: BTreeMap<AbilityKind, f32>,
+}
+
+impl Abilities {
+    pub fn new(known: Vec<AbilityKind>) -> Self {
+        Self { known, cooldowns: BTreeMap::new() }
+    }
+
+    pub fn is_ready(&self, kind: AbilityKind) -> bool {
+        self.known.contains(&kind) && !self.cooldowns.contains_key(&kind)
+    }
+
+    /// Count down every cooldown, forgetting those that have run out
+    pub fn update(&mut self, dt: DeltaTime) {
+        for remaining in self.cooldowns.values_mut() {
+            *remaining -= dt.0;
+        }
+
+        self.cooldowns.retain(|_, remaining| *remaining > 0.0);
+    }
+}
+
+/// An ability the player is picking a target for, and the tile they're aiming at
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Targeting {
+    pub ability: AbilityKind,
+    pub cursor: [i32; 2],
+}
+
+impl World {
+    /// Get the tiles an ability would affect if used by a caster on a tile
+    ///
+    /// Nothing is affected if the tile is out of range or out of the caster's sight.
+    pub fn ability_area(&self, caster: EntityId, kind: AbilityKind, target: [i32; 2]) -> Vec<[i32; 2]> {
+        let position = match self.positions.get(&caster) {
+            Some(position) => *position,
+            None => return vec![],
+        };
+
+        let range = kind.definition().range;
+        let [dx, dy] = [target[0] - position[0], target[1] - position[1]];
+        match kind.definition().shape {
+            Shape::Single if self.can_see(position, target, range) => vec![target],
+            Shape::Single => vec![],
+            Shape::Line => self.line_of_fire(position, target, range),
+            Shape::Cone if dx == 0 && dy == 0 => vec![],
+            Shape::Cone => {
+                let length = ((dx * dx + dy * dy) as f32).sqrt();
+                let direction = [dx as f32 / length, dy as f32 / length];
+                let min_cos = CONE_HALF_ANGLE.to_radians().cos();
+                circle(position, range)
+                    .filter(|p| {
+                        let offset = [(p[0] - position[0]) as f32, (p[1] - position[1]) as f32];
+                        let distance = (offset[0] * offset[0] + offset[1] * offset[1]).sqrt();
+                        distance > 0.0 && (offset[0] * direction[0] + offset[1] * direction[1]) / distance >= min_cos
+                    })
+                    .filter(|p| self.can_see(position, *p, range))
+                    .collect()
+            }
+            Shape::Burst(radius) if self.can_see(position, target, range) => {
+                circle(target, radius).filter(|p| self.can_see(target, *p, radius)).collect()
+            }
+            Shape::Burst(_) => vec![],
+        }
+    }
+
+    /// Use an ability on a tile, striking everyone caught in its area
+    ///
+    /// Returns whether it could be used, which it can't while cooling down or with nothing in range.
+    pub fn use_ability(&mut self, caster: EntityId, kind: AbilityKind, target: [i32; 2]) -> bool {
+        if !self.abilities.get(&caster).is_some_and(|abilities| abilities.is_ready(kind)) {
+            return false;
+        }
+
+        let area = self.ability_area(caster, kind, target);
+        if area.is_empty() {
+            return false;
+        }
+
+        let definition = kind.definition();
+        if let Some(abilities) = self.abilities.get_mut(&caster) {
+            abilities.cooldowns.insert(kind, definition.cooldown);
+        }
+
+        self.notify(Event::AbilityUsed(caster, kind));
+        let victims: Vec<EntityId> = self.positions
+            .iter()
+            .filter(|(id, p)| **id != caster && self.healths.contains_key(id) && area.contains(p))
+            .map(|(id, _)| *id)
+            .collect();
+
+        for defender in victims {
+            let defense = self.defense(defender);
+            let outcome = resolve_attack(self.rng(Stream::Combat), &definition.attack, &defense);
+            self.notify(Event::Attacked { attacker: caster, defender, outcome });
+            if let AttackOutcome::Hit(damage) | AttackOutcome::Critical(damage) = outcome {
+                self.strike(caster, defender, &definition.attack, damage);
+            }
+        }
+
+        true
+    }
+
+    /// Count down every entity's ability cooldowns
+    pub fn update_abilities(&mut self, dt: DeltaTime) {
+        for abilities in self.abilities.values_mut() {
+            abilities.update(dt);
+        }
+    }
+
+    /// Start aiming one of the player's abilities, returning whether it's ready to use
+    ///
+    /// The cursor starts on the closest hostile in sight, or on the player if there is none.
+    pub fn start_targeting(&mut self, index: usize) -> bool {
+        let kind = match self.abilities.get(&self.player).and_then(|abilities| abilities.known.get(index)) {
+            Some(kind) if self.abilities[&self.player].is_ready(*kind) => *kind,
+            _ => return false,
+        };
+
+        let cursor = match self.nearest_visible_hostile().or_else(|| self.positions.get(&self.player).copied()) {
+            Some(cursor) => cursor,
+            None => return false,
+        };
+
+        self.targeting = Some(Targeting { ability: kind, cursor });
+        true
+    }
+
+    pub fn move_cursor(&mut self, offset: [i32; 2]) {
+        if let Some(targeting) = self.targeting.as_mut() {
+            targeting.cursor = [targeting.cursor[0] + offset[0], targeting.cursor[1] + offset[1]];
+        }
+    }
+
+    /// Get a sprite for every tile the aimed ability would affect, along with the cursor itself
+    pub fn targeting_sprites(&self) -> Vec<Entity> {
+        let targeting = match &self.targeting {
+            Some(targeting) => targeting,
+            None => return vec![],
+        };
+
+        let sprite = |p: [i32; 2], atlas_position, color| Entity::new([p[0] as f32, p[1] as f32], atlas_position, [1, 1], color, None);
+        let mut sprites: Vec<Entity> = self
+            .ability_area(self.player, targeting.ability, targeting.cursor)
+            .into_iter()
+            .map(|p| sprite(p, AREA_SPRITE, AREA_COLOR))
+            .collect();
+
+        sprites.push(sprite(targeting.cursor, CURSOR_SPRITE, YELLOW));
+        sprites
+    }
+
+    /// Get the lines of the HUD while aiming an ability, or `None` if the player isn't aiming
+    pub fn targeting_lines(&self, rows: usize) -> Option<Vec<(String, u32)>> {
+        let targeting = self.targeting.as_ref()?;
+        let name = &targeting.ability.definition().name;
+        let mut lines = vec![(format!("Aiming {name}: arrows to move, Enter to use, Escape to cancel"), WHITE)];
+        lines.extend(self.hud_lines(rows.saturating_sub(1)));
+        Some(lines)
+    }
+
+    /// Describe the player's abilities and their keys, with the seconds left on any cooling down
+    pub fn ability_summary(&self) -> Option<String> {
+        let abilities = self.abilities.get(&self.player)?;
+        let summary: Vec<String> = abilities
+            .known
+            .iter()
+            .zip(ABILITY_KEYS)
+            .map(|(kind, key)| match abilities.cooldowns.get(kind) {
+                Some(remaining) => format!("{} {} ({})", key, kind.definition().name, remaining.ceil()),
+                None => format!("{} {}", key, kind.definition().name),
+            })
+            .collect();
+
+        (!summary.is_empty()).then(|| summary.join(", "))
+    }
+}
+
+/// Iterate over every tile within a circle
+fn circle(center: [i32; 2], radius: i32) -> impl Iterator<Item = [i32; 2]> {
+    (-radius..=radius)
+        .flat_map(move |y| (-radius..=radius).map(move |x| [x, y]))
+        .filter(move |[x, y]| x * x + y * y <= radius * radius)
+        .map(move |[x, y]| [center[0] + x, center[1] + y])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::health::Health;
+    use crate::status::StatusKind;
+    use crate::time::DeltaTime;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::Abilities;
+    use super::AbilityKind;
+
+    /// An open chunk with a wall down column 10
+    fn world() -> World {
+        let mut chunk = [[Tile::Planks as u8; 16]; 16];
+        for row in chunk.iter_mut() {
+            row[10] = Tile::Wall as u8;
+        }
+
+        let mut world = World::test(chunk, [4, 8]);
+        let player = world.player;
+        world.abilities.insert(player, Abilities::new(vec![AbilityKind::Firebolt, AbilityKind::ConeOfCold, AbilityKind::Fireball]));
+        world
+    }
+
+    #[test]
+    fn shapes() {
+        let world = world();
+        let player = world.player;
+
+        let line = world.ability_area(player, AbilityKind::Firebolt, [6, 8]);
+        assert_eq!(line, (5..10).map(|x| [x, 8]).collect::<Vec<_>>());
+
+        let cone = world.ability_area(player, AbilityKind::ConeOfCold, [5, 8]);
+        assert!(cone.contains(&[8, 8]) && cone.contains(&[7, 7]));
+        assert!(!cone.contains(&[4, 7]) && !cone.contains(&[3, 8]));
+
+        let burst = world.ability_area(player, AbilityKind::Fireball, [9, 8]);
+        assert!(burst.contains(&[7, 8]) && burst.contains(&[9, 10]));
+        assert!(!burst.contains(&[11, 8]));
+        assert!(world.ability_area(player, AbilityKind::Fireball, [12, 8]).is_empty());
+        assert_eq!(world.ability_area(player, AbilityKind::PoisonDart, [5, 8]).len(), 1);
+    }
+
+    #[test]
+    fn abilities_strike_and_cool_down() {
+        let mut world = world();
+        let player = world.player;
+        let goblin = world.spawn();
+        world.positions.insert(goblin, [8, 8]);
+        world.healths.insert(goblin, Health::new(20));
+
+        assert!(world.use_ability(player, AbilityKind::Fireball, [8, 8]));
+        assert_eq!(world.healths[&goblin].current, 14);
+        assert!(world.statuses[&goblin].effects.contains_key(&StatusKind::Burning));
+
+        assert!(!world.use_ability(player, AbilityKind::Fireball, [8, 8]));
+        assert!(!world.use_ability(player, AbilityKind::PoisonDart, [8, 8]));
+        world.update_abilities(DeltaTime(15.0));
+        assert!(world.use_ability(player, AbilityKind::Fireball, [8, 8]));
+    }
+
+    #[test]
+    fn targeting_previews_area() {
+        let mut world = world();
+        assert!(world.start_targeting(2));
+        assert_eq!(world.targeting.unwrap().cursor, [4, 8]);
+
+        world.move_cursor([4, 0]);
+        let area = world.ability_area(world.player, AbilityKind::Fireball, [8, 8]);
+        assert!(area.contains(&[8, 8]));
+        assert_eq!(world.targeting_sprites().len(), area.len() + 1);
+        assert!(!world.start_targeting(3));
+    }
+
+    #[test]
+    fn definitions_load() {
+        for kind in [AbilityKind::PoisonDart, AbilityKind::Firebolt, AbilityKind::ConeOfCold, AbilityKind::Fireball] {
+            assert!(!kind.definition().name.is_empty());
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ability::AbilityKind;
+use crate::ecs::EntityId;
+use crate::equipment::EquipmentSlot;
+use crate::trap::PASSIVE_SEARCH_CHANCE;
+use crate::trap::PASSIVE_SEARCH_RADIUS;
+use crate::trap::SEARCH_CHANCE;
+use crate::trap::SEARCH_RADIUS;
+use crate::world::World;
+
+/// The energy spent by performing a standard action
+pub const ACTION_COST: i32 = 100;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Action {
+    Wait,
+    Move([i32; 2]),
+    Attack(EntityId),
+    /// Launch a projectile towards a tile
+    Fire([i32; 2]),
+    /// Pick up whatever is lying underfoot
+    PickUp,
+    /// Drop an inventory slot
+    Drop(usize),
+    /// Use an item from an inventory slot
+    Use(usize),
+    /// Wear an item from an inventory slot
+    Equip(usize),
+    /// Take off whatever is worn in an equipment slot
+    Unequip(EquipmentSlot),
+    /// Open, close or pull whatever is on an adjacent tile
+    Interact([i32; 2]),
+    /// Chip away at an adjacent tile
+    Dig([i32; 2]),
+    /// Look around for hidden traps and doors
+    Search,
+    /// Use an ability on a tile
+    Ability(AbilityKind, [i32; 2]),
+}
+
+impl Action {
+    /// Get the energy spent by performing a specific action
+    pub fn cost(self) -> i32 {
+        match self {
+            Action::Wait => ACTION_COST,
+            Action::Move(_) => ACTION_COST,
+            Action::Attack(_) => ACTION_COST,
+            Action::Fire(_) => ACTION_COST,
+            Action::PickUp => ACTION_COST,
+            Action::Drop(_) => ACTION_COST,
+            Action::Use(_) => ACTION_COST,
+            Action::Equip(_) => ACTION_COST,
+            Action::Unequip(_) => ACTION_COST,
+            Action::Interact(_) => ACTION_COST,
+            Action::Dig(_) => ACTION_COST,
+            Action::Search => ACTION_COST,
+            Action::Ability(kind, _) => kind.definition().cost,
+        }
+    }
+
+    /// Get how loud a specific action is, as the number of tiles of open floor it can be heard across
+    pub fn noise(self) -> i32 {
+        match self {
+            Action::Wait => 0,
+            Action::Move(_) => 3,
+            Action::Attack(_) => 6,
+            Action::Fire(_) => 4,
+            Action::PickUp => 1,
+            Action::Drop(_) => 1,
+            Action::Use(_) => 1,
+            Action::Equip(_) => 1,
+            Action::Unequip(_) => 1,
+            Action::Interact(_) => 5,
+            Action::Dig(_) => 8,
+            Action::Search => 0,
+            Action::Ability(_, _) => 4,
+        }
+    }
+
+    /// Apply an action to the world, returning whether it took any time
+    pub fn perform(self, world: &mut World, actor: EntityId) -> bool {
+        match self {
+            Action::Wait => true,
+            Action::Move(direction) => {
+                let position = match world.positions.get(&actor) {
+                    Some(position) => *position,
+                    None => return false,
+                };
+
+                let target = [position[0] + direction[0], position[1] + direction[1]];
+
+                // Walking into something hostile attacks it instead, and into someone friendly talks to them
+                if let Some(other) = world.entity_at(target) {
+                    if actor == world.player && (world.start_conversation(other) || world.open_shop(other)) {
+                        return false;
+                    }
+
+                    if world.is_hostile(actor, other) && world.healths.contains_key(&other) {
+                        return Action::Attack(other).perform(world, actor);
+                    }
+                }
+
+                // As does walking into a closed door, lever or chest
+                if world.tile(target).is_some_and(|tile| tile.is_solid() && tile.toggled().is_some()) {
+                    return Action::Interact(target).perform(world, actor);
+                }
+
+                if !world.is_passable(target) {
+                    return false;
+                }
+
+                world.move_entity(actor, target);
+                world.trigger_trap(actor);
+                if actor == world.player {
+                    world.search(actor, PASSIVE_SEARCH_RADIUS, PASSIVE_SEARCH_CHANCE);
+                }
+
+                true
+            }
+            Action::Attack(target) => {
+                let (from, to) = match (world.positions.get(&actor), world.positions.get(&target)) {
+                    (Some(from), Some(to)) => (*from, *to),
+                    _ => return false,
+                };
+
+                if (from[0] - to[0]).abs() > 1 || (from[1] - to[1]).abs() > 1 {
+                    return false;
+                }
+
+                world.melee(actor, target).is_some()
+            }
+            Action::Fire(target) => world.fire(actor, target),
+            Action::PickUp => world.pick_up(actor),
+            Action::Drop(slot) => world.drop_item(actor, slot),
+            Action::Use(slot) => world.use_item(actor, slot),
+            Action::Equip(slot) => world.equip(actor, slot),
+            Action::Unequip(slot) => world.unequip(actor, slot),
+            Action::Interact(position) => world.interact(actor, position),
+            Action::Dig(position) => world.dig(actor, position),
+            Action::Search => {
+                world.search(actor, SEARCH_RADIUS, SEARCH_CHANCE);
+                true
+            }
+            Action::Ability(kind, target) => world.use_ability(actor, kind, target),
+        }
+    }
+}
+
+/// Actions waiting to be performed, shared by the player and every other actor
+#[derive(Debug, Default)]
+pub struct ActionQueue {
+    actions: VecDeque<(EntityId, Action)>,
+}
+
+impl ActionQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, actor: EntityId, action: Action) {
+        self.actions.push_back((actor, action));
+    }
+
+    /// Whether an actor has an action waiting to be performed
+    pub fn is_pending(&self, actor: EntityId) -> bool {
+        self.actions.iter().any(|(id, _)| *id == actor)
+    }
+
+    /// Remove and return the oldest action queued by an actor
+    pub fn take(&mut self, actor: EntityId) -> Option<Action> {
+        let index = self.actions.iter().position(|(id, _)| *id == actor)?;
+        self.actions.remove(index).map(|(_, action)| action)
+    }
+
+    pub fn clear(&mut self) {
+        self.actions.clear();
+    }
+}
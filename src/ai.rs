@@ -0,0 +1,291 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::action::Action;
+use crate::ecs::EntityId;
+use crate::pathfinding::DijkstraMap;
+use crate::pathfinding::PathOptions;
+use crate::pathfinding::find_path;
+use crate::rng::Stream;
+use crate::stealth::AWARENESS_DECAY;
+use crate::stealth::FULL_AWARENESS;
+use crate::world::World;
+
+const DIRECTIONS: [[i32; 2]; 8] = [
+    [0, -1],
+    [1, -1],
+    [1, 0],
+    [1, 1],
+    [0, 1],
+    [-1, 1],
+    [-1, 0],
+    [-1, -1],
+];
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AiState {
+    /// Amble around with no particular goal
+    Wander,
+    /// Head towards where the target was last seen
+    Chase { last_seen: [i32; 2] },
+    /// Close enough to the target to strike it
+    Attack,
+    /// Get as far from the target as possible
+    Flee,
+    /// Head back to the post after losing track of the target
+    Return,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Ai {
+    pub state: AiState,
+    /// Where the entity was stationed, and where it returns to when it loses its target
+    pub post: [i32; 2],
+    pub sight_radius: i32,
+    /// The Chebyshev distance from which the entity can attack
+    pub attack_range: i32,
+    /// The fraction of maximum health below which the entity flees
+    pub flee_threshold: f32,
+    /// The chance to stand still each turn while wandering
+    pub idle_chance: f32,
+    /// How sure the entity is that the player is around, up to `FULL_AWARENESS`
+    #[serde(default)]
+    pub awareness: i32,
+}
+
+impl Ai {
+    pub fn new(post: [i32; 2]) -> Self {
+        Self {
+            state: AiState::Wander,
+            post,
+            sight_radius: 8,
+            attack_range: 1,
+            flee_threshold: 0.25,
+            idle_chance: 0.5,
+            awareness: 0,
+        }
+    }
+}
+
+/// Decide the next action of an AI controlled entity, updating its state as it goes
+pub fn think(world: &mut World, id: EntityId) -> Action {
+    let (mut ai, position) = match (world.ais.get(&id), world.positions.get(&id)) {
+        (Some(ai), Some(position)) => (*ai, *position),
+        _ => return Action::Wait,
+    };
+
+    let sight_radius = world.sight_radius(id, ai.sight_radius);
+    let seen = world.positions.get(&world.player).copied().filter(|target| {
+        world.is_hostile(id, world.player) && world.can_see(position, *target, sight_radius)
+    });
+
+    // The player can only be noticed in plain sight, which is easier the better lit they are and
+    // the more aware the entity already was
+    match seen {
+        Some(target) => {
+            let chance = world.detection_chance(target) + ai.awareness as f32 / FULL_AWARENESS as f32;
+            if ai.awareness >= FULL_AWARENESS || world.rng(Stream::Ai).chance(chance) {
+                ai.awareness = FULL_AWARENESS;
+            }
+        }
+        None => ai.awareness = (ai.awareness - AWARENESS_DECAY).max(0),
+    }
+
+    // Anything else hostile is noticed as soon as it's seen, and the closest target is picked
+    let others = world.positions.iter().filter(|(other, p)| {
+        **other != world.player
+            && world.healths.contains_key(other)
+            && world.is_hostile(id, **other)
+            && world.can_see(position, **p, sight_radius)
+    });
+
+    let target = seen
+        .filter(|_| ai.awareness >= FULL_AWARENESS)
+        .map(|p| (world.player, p))
+        .into_iter()
+        .chain(others.map(|(other, p)| (*other, *p)))
+        .min_by_key(|(_, p)| chebyshev_distance(position, *p));
+    let (target_id, target) = (target.map(|(id, _)| id), target.map(|(_, p)| p));
+
+    let low_health = world.healths.get(&id).is_some_and(|health| health.fraction() < ai.flee_threshold);
+
+    ai.state = match (target, ai.state) {
+        (Some(_), _) if low_health => AiState::Flee,
+        (Some(target), _) if chebyshev_distance(position, target) <= ai.attack_range => AiState::Attack,
+        (Some(target), _) => AiState::Chase { last_seen: target },
+        (None, AiState::Chase { last_seen }) if last_seen != position => AiState::Chase { last_seen },
+        (None, AiState::Wander) => AiState::Wander,
+        (None, _) if position == ai.post => AiState::Wander,
+        (None, _) => AiState::Return,
+    };
+
+    let options = PathOptions::default();
+    let action = match ai.state {
+        AiState::Wander => {
+            if world.rng(Stream::Ai).chance(ai.idle_chance) {
+                Action::Wait
+            } else {
+                Action::Move(DIRECTIONS[world.rng(Stream::Ai).range(0..DIRECTIONS.len() as i32) as usize])
+            }
+        }
+        AiState::Chase { last_seen } => match step_towards(world, position, last_seen, &options) {
+            Some(action) => action,
+            // Somewhere that can't be reached, such as behind a closed door, is given up on
+            None => {
+                ai.state = AiState::Return;
+                step_towards(world, position, ai.post, &options).unwrap_or(Action::Wait)
+            }
+        },
+        AiState::Attack => match (target_id, target) {
+            (_, Some(target)) if chebyshev_distance(position, target) > 1 => Action::Fire(target),
+            (Some(target_id), _) => Action::Attack(target_id),
+            _ => Action::Wait,
+        },
+        AiState::Flee => {
+            let cost = |p| world.movement_cost(p);
+            let approach = DijkstraMap::new(&[target.unwrap()], &options, cost);
+            match approach.flee(&options, cost).descend(position, &options, cost) {
+                Some(next) => Action::Move([next[0] - position[0], next[1] - position[1]]),
+                None => Action::Wait,
+            }
+        }
+        AiState::Return => step_towards(world, position, ai.post, &options).unwrap_or(Action::Wait),
+    };
+
+    world.ais.insert(id, ai);
+    action
+}
+
+fn chebyshev_distance(a: [i32; 2], b: [i32; 2]) -> i32 {
+    (a[0] - b[0]).abs().max((a[1] - b[1]).abs())
+}
+
+/// Get the first step along a path to a goal, or `None` if it can't be reached
+fn step_towards(world: &World, position: [i32; 2], goal: [i32; 2], options: &PathOptions) -> Option<Action> {
+    let path = find_path(position, goal, options, |p| world.movement_cost(p))?;
+    match path.first() {
+        Some(next) => Some(Action::Move([next[0] - position[0], next[1] - position[1]])),
+        None => Some(Action::Wait),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::action::Action;
+    use crate::ecs::EntityId;
+    use crate::health::Health;
+    use crate::light::Light;
+    use crate::stealth::FULL_AWARENESS;
+    use crate::tile::Tile;
+    use crate::trap::TrapKind;
+    use crate::world::World;
+
+    use super::Ai;
+    use super::AiState;
+    use super::think;
+
+    const PLAYER: EntityId = EntityId(0);
+    const MONSTER: EntityId = EntityId(1);
+
+    /// A single open chunk with a wall running down column 8 from row 0 to row 12
+    fn world(player: [i32; 2], monster: [i32; 2]) -> World {
+        let mut chunk = [[Tile::Planks as u8; 16]; 16];
+        for row in chunk.iter_mut().take(13) {
+            row[8] = Tile::Wall as u8;
+        }
+
+        let mut world = World { seed: 7, ..World::test(chunk, player) };
+
+        // The player carries a bright light, so they're noticed as soon as they're seen
+        world.light_sources.insert(PLAYER, Light::new([player[0] as f32, player[1] as f32], [255, 255, 255], 255));
+
+        // The monster is spawned so that nothing spawned later by a test reuses its id
+        assert_eq!(world.spawn(), MONSTER);
+        world.positions.insert(MONSTER, monster);
+        world.ais.insert(MONSTER, Ai::new(monster));
+        world.healths.insert(MONSTER, Health::new(10));
+        world
+    }
+
+    #[test]
+    fn wander_is_deterministic() {
+        let mut a = world([2, 2], [12, 2]);
+        let mut b = world([2, 2], [12, 2]);
+
+        for _ in 0..20 {
+            assert_eq!(think(&mut a, MONSTER), think(&mut b, MONSTER));
+            assert_eq!(a.ais[&MONSTER].state, AiState::Wander);
+        }
+    }
+
+    #[test]
+    fn chase_when_seen() {
+        let mut world = world([10, 2], [14, 6]);
+        assert_eq!(think(&mut world, MONSTER), Action::Move([-1, -1]));
+        assert_eq!(world.ais[&MONSTER].state, AiState::Chase { last_seen: [10, 2] });
+    }
+
+    #[test]
+    fn unnoticed_in_the_dark() {
+        let mut world = world([10, 2], [14, 6]);
+        world.light_sources.clear();
+        think(&mut world, MONSTER);
+        assert_eq!(world.ais[&MONSTER].state, AiState::Wander);
+
+        world.ais.get_mut(&MONSTER).unwrap().awareness = FULL_AWARENESS;
+        think(&mut world, MONSTER);
+        assert_eq!(world.ais[&MONSTER].state, AiState::Chase { last_seen: [10, 2] });
+    }
+
+    #[test]
+    fn attack_in_range() {
+        let mut world = world([10, 2], [11, 3]);
+        assert_eq!(think(&mut world, MONSTER), Action::Attack(PLAYER));
+        assert_eq!(world.ais[&MONSTER].state, AiState::Attack);
+    }
+
+    #[test]
+    fn flee_at_low_health() {
+        let mut world = world([14, 2], [12, 2]);
+        world.healths.get_mut(&MONSTER).unwrap().current = 1;
+
+        match think(&mut world, MONSTER) {
+            Action::Move([dx, _]) => assert_eq!(dx, -1),
+            action => panic!("{action:?}"),
+        }
+
+        assert_eq!(world.ais[&MONSTER].state, AiState::Flee);
+    }
+
+    #[test]
+    fn return_to_post() {
+        let mut world = world([2, 2], [12, 2]);
+        world.ais.get_mut(&MONSTER).unwrap().state = AiState::Chase { last_seen: [12, 6] };
+        world.positions.insert(MONSTER, [12, 6]);
+
+        assert_eq!(think(&mut world, MONSTER), Action::Move([0, -1]));
+        assert_eq!(world.ais[&MONSTER].state, AiState::Return);
+
+        world.positions.insert(MONSTER, [12, 2]);
+        think(&mut world, MONSTER);
+        assert_eq!(world.ais[&MONSTER].state, AiState::Wander);
+    }
+
+    #[test]
+    fn give_up_on_unreachable_noise() {
+        let mut world = world([2, 2], [6, 6]);
+        for position in [[1, 1], [2, 1], [3, 1], [1, 2], [1, 3], [2, 3], [3, 3]] {
+            world.set_tile(position, Tile::Wall);
+        }
+        world.set_tile([3, 2], Tile::DoorClosed);
+        world.positions.insert(MONSTER, [6, 4]);
+
+        // The alarm is heard through the door, but the closet it went off in can't be walked into
+        world.spawn_trap(TrapKind::Alarm, [2, 2], false);
+        world.trigger_trap(PLAYER);
+        assert_eq!(world.ais[&MONSTER].state, AiState::Chase { last_seen: [2, 2] });
+
+        assert_eq!(think(&mut world, MONSTER), Action::Move([0, 1]));
+        assert_eq!(world.ais[&MONSTER].state, AiState::Return);
+    }
+}
@@ -1,6 +1,38 @@
+use bytemuck::Pod;
+use bytemuck::Zeroable;
+use serde::Deserialize;
+use serde::Serialize;
 use wgpu::Color;
 
 pub const CHUNK_SIZE: u32 = 16;
 pub const CHUNK_CLEAR_COLOR: Color = Color { r: 0.01, g: 0.01, b: 0.01, a: 0.0 };
 
 pub type Chunk = [[u8; CHUNK_SIZE as usize]; CHUNK_SIZE as usize];
+/// The damage taken by each tile of a chunk
+pub type ChunkDamage = [[u32; CHUNK_SIZE as usize]; CHUNK_SIZE as usize];
+
+/// Split a tile position into the position of its chunk and its position within that chunk
+pub fn split_position(position: [i32; 2]) -> ([i32; 2], [usize; 2]) {
+    let size = CHUNK_SIZE as i32;
+    (
+        [position[0].div_euclid(size), position[1].div_euclid(size)],
+        [position[0].rem_euclid(size) as usize, position[1].rem_euclid(size) as usize],
+    )
+}
+
+/// One bit per tile of a chunk, in the same row-major order as the chunk itself
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Pod, Serialize, Zeroable)]
+pub struct ChunkMask(pub [u32; (CHUNK_SIZE * CHUNK_SIZE / 32) as usize]);
+
+impl ChunkMask {
+    pub fn get(&self, [x, y]: [usize; 2]) -> bool {
+        let bit = y * CHUNK_SIZE as usize + x;
+        self.0[bit / 32] & (1 << (bit % 32)) != 0
+    }
+
+    pub fn set(&mut self, [x, y]: [usize; 2]) {
+        let bit = y * CHUNK_SIZE as usize + x;
+        self.0[bit / 32] |= 1 << (bit % 32);
+    }
+}
@@ -0,0 +1,247 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ecs::EntityId;
+use crate::entity::Entity;
+use crate::log::Event;
+use crate::rng::Rng;
+use crate::rng::Stream;
+use crate::status::StatusKind;
+use crate::trap::TrapKind;
+use crate::world::World;
+
+/// Hit chances are clamped to this many percent away from certainty
+const MIN_HIT_CHANCE: i32 = 5;
+const CRITICAL_MULTIPLIER: i32 = 2;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Attack {
+    pub damage: i32,
+    /// The base chance to hit in percent, before the target's evasion is subtracted
+    pub accuracy: i32,
+    /// The chance in percent that a hit is critical
+    pub critical_chance: i32,
+    /// A status inflicted on whoever is hit
+    pub effect: Option<StatusKind>,
+}
+
+impl Default for Attack {
+    fn default() -> Self {
+        Self { damage: 2, accuracy: 80, critical_chance: 5, effect: None }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Defense {
+    /// Subtracted from the damage of every hit
+    pub armor: i32,
+    /// Subtracted from the attacker's accuracy
+    pub evasion: i32,
+}
+
+/// What an entity leaves behind when it dies
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Corpse {
+    pub atlas_position: [u32; 2],
+    pub color: u32,
+}
+
+/// What dealt damage to an entity, used to credit kills and describe deaths
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DamageSource {
+    Entity(EntityId),
+    Status(StatusKind),
+    /// An explosion, along with whoever set it off
+    Explosion(Option<EntityId>),
+    Trap(TrapKind),
+}
+
+impl DamageSource {
+    /// Get the entity responsible for the damage, if any
+    pub fn culprit(self) -> Option<EntityId> {
+        match self {
+            DamageSource::Entity(id) => Some(id),
+            DamageSource::Status(_) => None,
+            DamageSource::Explosion(id) => id,
+            DamageSource::Trap(_) => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AttackOutcome {
+    Miss,
+    Hit(i32),
+    Critical(i32),
+}
+
+/// Get the chance in percent that an attack hits
+pub fn hit_chance(attack: &Attack, defense: &Defense) -> i32 {
+    (attack.accuracy - defense.evasion).clamp(MIN_HIT_CHANCE, 100 - MIN_HIT_CHANCE)
+}
+
+/// Get the damage dealt by a hit, which is always at least 1
+pub fn damage(attack: &Attack, defense: &Defense, critical: bool) -> i32 {
+    let damage = if critical { attack.damage * CRITICAL_MULTIPLIER } else { attack.damage };
+    (damage - defense.armor).max(1)
+}
+
+/// Roll whether an attack hits and how hard
+pub fn resolve_attack(rng: &mut Rng, attack: &Attack, defense: &Defense) -> AttackOutcome {
+    if rng.range(0..100) >= hit_chance(attack, defense) {
+        return AttackOutcome::Miss;
+    }
+
+    if rng.range(0..100) < attack.critical_chance {
+        AttackOutcome::Critical(damage(attack, defense, true))
+    } else {
+        AttackOutcome::Hit(damage(attack, defense, false))
+    }
+}
+
+impl World {
+    /// Have one entity strike another, returning `None` if it couldn't attack at all
+    pub fn melee(&mut self, attacker: EntityId, defender: EntityId) -> Option<AttackOutcome> {
+        let attack = self.attack(attacker)?;
+        let defense = self.defense(defender);
+        self.healths.get(&defender)?;
+
+        let outcome = resolve_attack(self.rng(Stream::Combat), &attack, &defense);
+        self.notify(Event::Attacked { attacker, defender, outcome });
+        if let AttackOutcome::Hit(damage) | AttackOutcome::Critical(damage) = outcome {
+            self.strike(attacker, defender, &attack, damage);
+        }
+
+        Some(outcome)
+    }
+
+    /// Deal the damage of an attack that landed, along with any status it inflicts
+    pub fn strike(&mut self, attacker: EntityId, defender: EntityId, attack: &Attack, damage: i32) {
+        if let Some(effect) = attack.effect {
+            self.apply_status(defender, effect);
+        }
+
+        if attacker == self.player {
+            self.offend(defender, false);
+        }
+
+        self.damage(defender, damage, DamageSource::Entity(attacker));
+    }
+
+    /// Reduce an entity's health, killing it if none is left
+    pub fn damage(&mut self, id: EntityId, damage: i32, source: DamageSource) {
+        let dead = match self.healths.get_mut(&id) {
+            Some(health) => {
+                health.current -= damage;
+                health.is_dead()
+            }
+            None => false,
+        };
+
+        if dead {
+            self.kill(id, source);
+        }
+    }
+
+    /// Remove an entity from the world, leaving its corpse and loot behind and rewarding the player
+    ///
+    /// The player is never removed. Their death ends the run instead, keeping everything they
+    /// carried around for the morgue file.
+    pub fn kill(&mut self, id: EntityId, source: DamageSource) {
+        if id == self.player {
+            if !self.is_dead() {
+                self.stats.cause_of_death = Some(self.describe_death(source));
+                self.notify(Event::Killed(id));
+            }
+
+            return;
+        }
+
+        self.notify(Event::Killed(id));
+        if source.culprit() == Some(self.player) {
+            let name = self.names.get(&id).cloned().unwrap_or_else(|| "something".to_string());
+            *self.stats.kills.entry(name).or_default() += 1;
+            self.offend(id, true);
+        }
+
+        if let (Some(corpse), Some(position)) = (self.corpses.get(&id).copied(), self.positions.get(&id).copied()) {
+            let remains = self.spawn();
+            let position = [position[0] as f32, position[1] as f32];
+            self.entities.insert(remains, Entity::new(position, corpse.atlas_position, [1, 1], corpse.color, None));
+        }
+
+        if let (Some(loot), Some(position)) = (self.loot.get(&id).copied(), self.positions.get(&id).copied()) {
+            self.drop_loot(loot.table, position, &loot.source());
+        }
+
+        self.reward_kill(id, source);
+        self.despawn(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecs::EntityId;
+    use crate::health::Health;
+    use crate::rng::Rng;
+    use crate::world::World;
+
+    use super::Attack;
+    use super::AttackOutcome;
+    use super::Corpse;
+    use super::DamageSource;
+    use super::Defense;
+    use super::damage;
+    use super::hit_chance;
+    use super::resolve_attack;
+
+    #[test]
+    fn damage_formula() {
+        let attack = Attack { damage: 5, ..Default::default() };
+        assert_eq!(damage(&attack, &Defense::default(), false), 5);
+        assert_eq!(damage(&attack, &Defense { armor: 2, evasion: 0 }, false), 3);
+        assert_eq!(damage(&attack, &Defense { armor: 2, evasion: 0 }, true), 8);
+        assert_eq!(damage(&attack, &Defense { armor: 20, evasion: 0 }, false), 1);
+    }
+
+    #[test]
+    fn hit_chance_is_clamped() {
+        let attack = Attack { accuracy: 80, ..Default::default() };
+        assert_eq!(hit_chance(&attack, &Defense { armor: 0, evasion: 30 }), 50);
+        assert_eq!(hit_chance(&attack, &Defense { armor: 0, evasion: 100 }), 5);
+        assert_eq!(hit_chance(&Attack { accuracy: 200, ..attack }, &Defense::default()), 95);
+    }
+
+    #[test]
+    fn resolution_is_deterministic() {
+        let attack = Attack::default();
+        let defense = Defense { armor: 0, evasion: 30 };
+        let rolls = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..50).map(|_| resolve_attack(&mut rng, &attack, &defense)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(rolls(3), rolls(3));
+        assert!(rolls(3).contains(&AttackOutcome::Miss));
+        assert!(rolls(3).contains(&AttackOutcome::Hit(2)));
+    }
+
+    #[test]
+    fn death_leaves_corpse() {
+        let mut world = World::default();
+        let victim = world.spawn();
+        world.positions.insert(victim, [3, 4]);
+        world.healths.insert(victim, Health::new(5));
+        world.corpses.insert(victim, Corpse { atlas_position: [2, 0], color: 0 });
+
+        world.damage(victim, 4, DamageSource::Explosion(None));
+        assert_eq!(world.healths[&victim].current, 1);
+
+        world.damage(victim, 4, DamageSource::Explosion(None));
+        assert!(!world.positions.contains_key(&victim));
+        assert!(!world.healths.contains_key(&victim));
+        assert_eq!(world.entities.len(), 1);
+        assert_ne!(world.entities.keys().next(), Some(&victim));
+        assert_eq!(world.entity_at([3, 4]), None::<EntityId>);
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ecs::EntityId;
+use crate::error::Error;
+use crate::faction::Faction;
+use crate::item::Item;
+use crate::item::ItemKind;
+use crate::log::Event;
+use crate::log::GREY;
+use crate::log::WHITE;
+use crate::log::YELLOW;
+use crate::world::World;
+
+/// Something that has to be true of the player for a choice to be offered
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Condition {
+    HasItem { kind: ItemKind, count: u32 },
+    /// The player is at least this level
+    Level(u32),
+    Flag(String),
+    NotFlag(String),
+}
+
+/// Something that happens when a choice is picked
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Effect {
+    GiveItem { kind: ItemKind, count: u32 },
+    TakeItem { kind: ItemKind, count: u32 },
+    SetFlag(String),
+    /// Shift the player's reputation with a faction
+    Reputation { faction: Faction, change: i32 },
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Choice {
+    pub text: String,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    /// The node the conversation moves on to, or `None` to end it
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Node {
+    pub text: String,
+    pub choices: Vec<Choice>,
+}
+
+/// A tree of things an NPC can say, keyed by node name
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Dialogue {
+    pub start: String,
+    pub nodes: BTreeMap<String, Node>,
+}
+
+impl Dialogue {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// A conversation the player is having, and the response they have highlighted
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Conversation {
+    pub speaker: EntityId,
+    pub node: String,
+    pub selected: usize,
+}
+
+impl World {
+    /// Whether a condition holds for the player
+    pub fn check(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::HasItem { kind, count } => {
+                self.inventories.get(&self.player).is_some_and(|inventory| inventory.count(*kind) >= *count)
+            }
+            Condition::Level(level) => self.progressions.get(&self.player).is_some_and(|p| p.level >= *level),
+            Condition::Flag(flag) => self.flags.contains(flag),
+            Condition::NotFlag(flag) => !self.flags.contains(flag),
+        }
+    }
+
+    pub fn apply(&mut self, effect: &Effect) {
+        match effect {
+            Effect::GiveItem { kind, count } => {
+                let item = Item::new(*kind, *count);
+                self.notify(Event::Received(item.clone()));
+                self.give_player(item);
+            }
+            Effect::TakeItem { kind, count } => {
+                let removed = self.inventories.get_mut(&self.player).is_some_and(|i| i.remove(*kind, *count));
+                if removed {
+                    self.notify(Event::HandedOver(Item::new(*kind, *count)));
+                }
+            }
+            Effect::SetFlag(flag) => {
+                self.flags.insert(flag.clone());
+            }
+            Effect::Reputation { faction, change } => self.change_reputation(*faction, *change),
+        }
+    }
+
+    /// Start talking to an entity, returning whether it had anything to say
+    ///
+    /// Nobody talks to the player while they're hostile towards them.
+    pub fn start_conversation(&mut self, speaker: EntityId) -> bool {
+        let start = match self.dialogues.get(&speaker) {
+            Some(dialogue) if !self.is_hostile(speaker, self.player) => dialogue.start.clone(),
+            _ => return false,
+        };
+
+        self.conversation = Some(Conversation { speaker, node: start, selected: 0 });
+        true
+    }
+
+    /// Get the node the current conversation is at
+    pub fn dialogue_node(&self) -> Option<&Node> {
+        let conversation = self.conversation.as_ref()?;
+        self.dialogues.get(&conversation.speaker)?.nodes.get(&conversation.node)
+    }
+
+    /// Get the choices the player can currently pick from, in order
+    pub fn dialogue_choices(&self) -> Vec<&Choice> {
+        match self.dialogue_node() {
+            Some(node) => node.choices.iter().filter(|c| c.conditions.iter().all(|c| self.check(c))).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Move the highlighted response up or down, wrapping around
+    pub fn select_choice(&mut self, offset: i32) {
+        let count = self.dialogue_choices().len() as i32;
+        if let Some(conversation) = self.conversation.as_mut() {
+            if count > 0 {
+                conversation.selected = (conversation.selected as i32 + offset).rem_euclid(count) as usize;
+            }
+        }
+    }
+
+    /// Pick the highlighted response, applying its effects and moving the conversation along
+    pub fn confirm_choice(&mut self) {
+        let selected = match &self.conversation {
+            Some(conversation) => conversation.selected,
+            None => return,
+        };
+
+        let choice = match self.dialogue_choices().get(selected) {
+            Some(choice) => (*choice).clone(),
+            None => {
+                self.conversation = None;
+                return;
+            }
+        };
+
+        for effect in &choice.effects {
+            self.apply(effect);
+        }
+
+        match (choice.next, self.conversation.as_mut()) {
+            (Some(next), Some(conversation)) => {
+                conversation.node = next;
+                conversation.selected = 0;
+            }
+            _ => self.conversation = None,
+        }
+    }
+
+    /// Get the lines of the dialogue panel, or `None` if the player isn't talking to anyone
+    pub fn dialogue_lines(&self) -> Option<Vec<(String, u32)>> {
+        let conversation = self.conversation.as_ref()?;
+        let node = self.dialogue_node()?;
+
+        let speaker = self.names.get(&conversation.speaker).map(String::as_str).unwrap_or("someone");
+        let mut lines = vec![(format!("{}: {}", speaker, node.text), WHITE)];
+        for (index, choice) in self.dialogue_choices().into_iter().enumerate() {
+            match index == conversation.selected {
+                true => lines.push((format!("> {}", choice.text), YELLOW)),
+                false => lines.push((format!("  {}", choice.text), GREY)),
+            }
+        }
+
+        Some(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecs::EntityId;
+    use crate::faction::Faction;
+    use crate::inventory::Inventory;
+    use crate::item::Item;
+    use crate::item::ItemKind;
+    use crate::world::World;
+
+    use super::Dialogue;
+
+    const ELDER: &str = r#"{
+        "start": "greeting",
+        "nodes": {
+            "greeting": {
+                "text": "Bring me a rock.",
+                "choices": [
+                    {
+                        "text": "Here you go.",
+                        "conditions": [{ "HasItem": { "kind": "Rock", "count": 1 } }],
+                        "effects": [{ "TakeItem": { "kind": "Rock", "count": 1 } }, { "SetFlag": "rock" }],
+                        "next": "thanks"
+                    },
+                    { "text": "Goodbye." }
+                ]
+            },
+            "thanks": {
+                "text": "Take this.",
+                "choices": [{ "text": "Thanks.", "effects": [{ "GiveItem": { "kind": "HealthPotion", "count": 2 } }] }]
+            }
+        }
+    }"#;
+
+    fn world() -> (World, EntityId) {
+        let mut world = World::default();
+        let player = world.player;
+        world.inventories.insert(player, Inventory::new(4, 100));
+
+        let elder = world.spawn();
+        world.names.insert(elder, "elder".to_string());
+        world.factions.insert(elder, Faction::Townsfolk);
+        world.dialogues.insert(elder, serde_json::from_str::<Dialogue>(ELDER).unwrap());
+        (world, elder)
+    }
+
+    #[test]
+    fn conditions_hide_choices() {
+        let (mut world, elder) = world();
+        assert!(world.start_conversation(elder));
+        assert_eq!(world.dialogue_choices().len(), 1);
+        assert_eq!(world.dialogue_lines().unwrap()[0].0, "elder: Bring me a rock.");
+
+        world.confirm_choice();
+        assert!(world.conversation.is_none());
+        assert!(!world.start_conversation(world.player));
+
+        world.change_reputation(Faction::Townsfolk, -100);
+        assert!(!world.start_conversation(elder));
+    }
+
+    #[test]
+    fn effects_apply() {
+        let (mut world, elder) = world();
+        let player = world.player;
+        world.inventories.get_mut(&player).unwrap().add(Item::new(ItemKind::Rock, 2)).unwrap();
+
+        world.start_conversation(elder);
+        assert_eq!(world.dialogue_choices().len(), 2);
+        world.select_choice(-2);
+        world.confirm_choice();
+        assert_eq!(world.conversation.as_ref().unwrap().node, "thanks");
+        assert!(world.flags.contains("rock"));
+
+        world.confirm_choice();
+        assert!(world.conversation.is_none());
+        assert_eq!(world.inventories[&player].count(ItemKind::Rock), 1);
+        assert_eq!(world.inventories[&player].count(ItemKind::HealthPotion), 2);
+    }
+}
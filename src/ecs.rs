@@ -1,5 +1,15 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serialize;
 use winit::dpi::PhysicalSize;
 
+/// Storage for a single kind of component, keyed by the entity that owns it
+pub type Components<T> = BTreeMap<EntityId, T>;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct EntityId(pub u32);
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Resolution {
     pub width: u32,
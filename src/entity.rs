@@ -26,4 +26,12 @@ impl Entity {
             detail: detail.unwrap_or(color),
         }
     }
+
+    pub fn position(&self) -> [f32; 2] {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: [f32; 2]) {
+        self.position = position;
+    }
 }
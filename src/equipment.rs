@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::ops::Add;
+use std::ops::Mul;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat::Attack;
+use crate::combat::Defense;
+use crate::ecs::EntityId;
+use crate::item::Item;
+use crate::light::Light;
+use crate::log::Event;
+use crate::world::World;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum EquipmentSlot {
+    Weapon,
+    Armor,
+    Ring,
+    LightSource,
+}
+
+/// Adjustments that equipment and statuses make to an entity's stats
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Modifiers {
+    pub damage: i32,
+    pub accuracy: i32,
+    pub armor: i32,
+    pub evasion: i32,
+    pub speed: i32,
+    /// Added to the radius an entity can see
+    pub sight: i32,
+    /// Added to the chance in percent of spotting hidden traps and doors
+    pub perception: i32,
+}
+
+impl Add for Modifiers {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            damage: self.damage + other.damage,
+            accuracy: self.accuracy + other.accuracy,
+            armor: self.armor + other.armor,
+            evasion: self.evasion + other.evasion,
+            speed: self.speed + other.speed,
+            sight: self.sight + other.sight,
+            perception: self.perception + other.perception,
+        }
+    }
+}
+
+impl Mul<i32> for Modifiers {
+    type Output = Self;
+
+    fn mul(self, scale: i32) -> Self {
+        Self {
+            damage: self.damage * scale,
+            accuracy: self.accuracy * scale,
+            armor: self.armor * scale,
+            evasion: self.evasion * scale,
+            speed: self.speed * scale,
+            sight: self.sight * scale,
+            perception: self.perception * scale,
+        }
+    }
+}
+
+/// The items an entity is wearing, at most one per slot
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Equipment {
+    pub slots: BTreeMap<EquipmentSlot, Item>,
+}
+
+impl Equipment {
+    pub fn modifiers(&self) -> Modifiers {
+        self.slots.values().fold(Modifiers::default(), |total, item| total + item.modifiers())
+    }
+}
+
+impl World {
+    /// Get the sum of the modifiers granted by an entity's equipment, statuses and perks
+    pub fn modifiers(&self, id: EntityId) -> Modifiers {
+        let equipment = self.equipment.get(&id).map(|equipment| equipment.modifiers()).unwrap_or_default();
+        let statuses = self.statuses.get(&id).map(|statuses| statuses.modifiers()).unwrap_or_default();
+        let perks = self.progressions.get(&id).map(|progression| progression.modifiers()).unwrap_or_default();
+        equipment + statuses + perks
+    }
+
+    /// Get an entity's attack after its equipment and statuses are taken into account
+    pub fn attack(&self, id: EntityId) -> Option<Attack> {
+        let attack = self.attacks.get(&id)?;
+        let modifiers = self.modifiers(id);
+        Some(Attack {
+            damage: attack.damage + modifiers.damage,
+            accuracy: attack.accuracy + modifiers.accuracy,
+            ..*attack
+        })
+    }
+
+    /// Get an entity's defense after its equipment and statuses are taken into account
+    pub fn defense(&self, id: EntityId) -> Defense {
+        let defense = self.defenses.get(&id).copied().unwrap_or_default();
+        let modifiers = self.modifiers(id);
+        Defense {
+            armor: defense.armor + modifiers.armor,
+            evasion: defense.evasion + modifiers.evasion,
+        }
+    }
+
+    /// Get the energy an entity gains per tick after its equipment and statuses are taken into account
+    pub fn speed(&self, id: EntityId) -> i32 {
+        let speed = self.energies.get(&id).map_or(0, |energy| energy.speed);
+        (speed + self.modifiers(id).speed).max(1)
+    }
+
+    /// Get the radius an entity can see, given its usual radius
+    pub fn sight_radius(&self, id: EntityId, radius: i32) -> i32 {
+        (radius + self.modifiers(id).sight).max(1)
+    }
+
+    /// Wear a single item from an inventory slot, swapping out whatever was worn before
+    pub fn equip(&mut self, actor: EntityId, slot: usize) -> bool {
+        let (inventory, equipment) = match (self.inventories.get_mut(&actor), self.equipment.get_mut(&actor)) {
+            (Some(inventory), Some(equipment)) => (inventory, equipment),
+            _ => return false,
+        };
+
+        let equipment_slot = match inventory.items.get(slot).and_then(|item| item.kind.slot()) {
+            Some(equipment_slot) => equipment_slot,
+            None => return false,
+        };
+
+        let item = inventory.take(slot, 1).unwrap();
+        let kind = item.kind;
+        if let Some(previous) = equipment.slots.insert(equipment_slot, item) {
+            if let Err(previous) = inventory.add(previous) {
+                // There's no room for what was already worn, so put everything back
+                let item = equipment.slots.insert(equipment_slot, previous).unwrap();
+                inventory.add(item).unwrap();
+                return false;
+            }
+        }
+
+        self.update_light_source(actor);
+        self.notify(Event::Equipped(actor, kind));
+        true
+    }
+
+    /// Take off whatever is worn in a slot and put it back in the inventory
+    pub fn unequip(&mut self, actor: EntityId, equipment_slot: EquipmentSlot) -> bool {
+        let (inventory, equipment) = match (self.inventories.get_mut(&actor), self.equipment.get_mut(&actor)) {
+            (Some(inventory), Some(equipment)) => (inventory, equipment),
+            _ => return false,
+        };
+
+        let item = match equipment.slots.remove(&equipment_slot) {
+            Some(item) => item,
+            None => return false,
+        };
+
+        if let Err(item) = inventory.add(item) {
+            equipment.slots.insert(equipment_slot, item);
+            return false;
+        }
+
+        self.update_light_source(actor);
+        true
+    }
+
+    /// Attach or detach the light given off by whatever an entity holds as a light source
+    fn update_light_source(&mut self, id: EntityId) {
+        let light = self.equipment
+            .get(&id)
+            .and_then(|equipment| equipment.slots.get(&EquipmentSlot::LightSource))
+            .and_then(|item| item.kind.light());
+
+        match (light, self.positions.get(&id)) {
+            (Some((color, magnitude)), Some(position)) => {
+                let position = [position[0] as f32, position[1] as f32];
+                self.light_sources.insert(id, Light::new(position, color, magnitude));
+            }
+            _ => {
+                self.light_sources.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::combat::Attack;
+    use crate::inventory::Inventory;
+    use crate::item::Item;
+    use crate::item::ItemKind;
+    use crate::scheduler::Energy;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::Equipment;
+    use super::EquipmentSlot;
+
+    fn world() -> World {
+        let mut world = World::test([[Tile::Planks as u8; 16]; 16], [2, 3]);
+        let player = world.player;
+        world.attacks.insert(player, Attack::default());
+        world.energies.insert(player, Energy::new(10));
+        world.inventories.insert(player, Inventory::new(4, 100));
+        world.equipment.insert(player, Equipment::default());
+        world
+    }
+
+    #[test]
+    fn modifiers_feed_stats() {
+        let mut world = world();
+        let player = world.player;
+        let inventory = world.inventories.get_mut(&player).unwrap();
+        inventory.add(Item::new(ItemKind::Dagger, 1)).unwrap();
+        inventory.add(Item::new(ItemKind::LeatherArmor, 1)).unwrap();
+
+        assert!(world.equip(player, 0));
+        assert!(world.equip(player, 0));
+        assert!(world.inventories[&player].items.is_empty());
+        assert_eq!(world.attack(player).unwrap().damage, Attack::default().damage + 2);
+        assert_eq!(world.defense(player).armor, 2);
+        assert_eq!(world.speed(player), 9);
+    }
+
+    #[test]
+    fn slot_rules() {
+        let mut world = world();
+        let player = world.player;
+        let inventory = world.inventories.get_mut(&player).unwrap();
+        inventory.add(Item::new(ItemKind::HealthPotion, 1)).unwrap();
+        inventory.add(Item::new(ItemKind::Dagger, 2)).unwrap();
+
+        assert!(!world.equip(player, 0));
+        assert!(world.equip(player, 1));
+        assert!(world.equip(player, 1));
+        assert_eq!(world.equipment[&player].slots[&EquipmentSlot::Weapon], Item::new(ItemKind::Dagger, 1));
+        assert_eq!(world.inventories[&player].items.len(), 2);
+
+        assert!(world.unequip(player, EquipmentSlot::Weapon));
+        assert!(!world.unequip(player, EquipmentSlot::Weapon));
+        assert_eq!(world.inventories[&player].items.len(), 3);
+    }
+
+    #[test]
+    fn torch_attaches_light() {
+        let mut world = world();
+        let player = world.player;
+        world.inventories.get_mut(&player).unwrap().add(Item::new(ItemKind::Torch, 1)).unwrap();
+
+        assert!(world.equip(player, 0));
+        assert!(world.light_sources.contains_key(&player));
+
+        world.move_entity(player, [3, 3]);
+        assert_eq!(world.light_sources[&player].position(), [3.0, 3.0]);
+
+        assert!(world.unequip(player, EquipmentSlot::LightSource));
+        assert!(!world.light_sources.contains_key(&player));
+    }
+}
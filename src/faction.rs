@@ -0,0 +1,199 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ecs::EntityId;
+use crate::log::Event;
+use crate::world::World;
+
+/// Reputation lost with a faction whenever the player attacks one of its members
+pub const ATTACK_PENALTY: i32 = 20;
+/// Reputation lost with a faction whenever the player kills one of its members
+pub const KILL_PENALTY: i32 = 40;
+/// Reputation gained with every faction that hated whoever the player killed
+pub const RIVAL_KILL_BONUS: i32 = 10;
+/// The furthest reputation can move from a faction's starting attitude in either direction
+const MAX_REPUTATION: i32 = 200;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Faction {
+    Player,
+    Townsfolk,
+    Beasts,
+    Goblins,
+    Orcs,
+}
+
+impl Faction {
+    pub const ALL: [Faction; 5] = [Faction::Player, Faction::Townsfolk, Faction::Beasts, Faction::Goblins, Faction::Orcs];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Faction::Player => "you",
+            Faction::Townsfolk => "townsfolk",
+            Faction::Beasts => "beasts",
+            Faction::Goblins => "goblins",
+            Faction::Orcs => "orcs",
+        }
+    }
+
+    /// Get how two factions feel about each other before the player's reputation comes into it
+    ///
+    /// Ranges from -100 for sworn enemies to 100 for allies, and anything below zero is hostile.
+    pub fn attitude(self, other: Faction) -> i32 {
+        let pair = if self <= other { (self, other) } else { (other, self) };
+        match pair {
+            (a, b) if a == b => 100,
+            (Faction::Player, Faction::Townsfolk) => 50,
+            (Faction::Player, Faction::Beasts) => -100,
+            (Faction::Player, Faction::Goblins) => -50,
+            (Faction::Player, Faction::Orcs) => -100,
+            (Faction::Townsfolk, Faction::Beasts) => -50,
+            (Faction::Townsfolk, Faction::Goblins) => -50,
+            (Faction::Townsfolk, Faction::Orcs) => -100,
+            (Faction::Goblins, Faction::Orcs) => -50,
+            _ => 0,
+        }
+    }
+}
+
+impl World {
+    /// Get the faction an entity belongs to, which for the player is always their own
+    pub fn faction(&self, id: EntityId) -> Option<Faction> {
+        match id == self.player {
+            true => Some(Faction::Player),
+            false => self.factions.get(&id).copied(),
+        }
+    }
+
+    /// Get how a faction feels about the player, taking their reputation into account
+    pub fn standing(&self, faction: Faction) -> i32 {
+        Faction::Player.attitude(faction) + self.reputation.get(&faction).copied().unwrap_or_default()
+    }
+
+    /// Get how two factions feel about each other, from the relationship matrix or the player's standing
+    pub fn relation(&self, a: Faction, b: Faction) -> i32 {
+        match (a, b) {
+            (Faction::Player, Faction::Player) => 100,
+            (Faction::Player, other) | (other, Faction::Player) => self.standing(other),
+            _ => a.attitude(b),
+        }
+    }
+
+    /// Whether two entities will fight each other
+    ///
+    /// Entities without a faction only fight the player.
+    pub fn is_hostile(&self, a: EntityId, b: EntityId) -> bool {
+        if a == b {
+            return false;
+        }
+
+        match (self.faction(a), self.faction(b)) {
+            (Some(a), Some(b)) => self.relation(a, b) < 0,
+            _ => a == self.player || b == self.player,
+        }
+    }
+
+    /// Shift the player's reputation with a faction, announcing it if they're now treated differently
+    pub fn change_reputation(&mut self, faction: Faction, change: i32) {
+        if faction == Faction::Player || change == 0 {
+            return;
+        }
+
+        let was_hostile = self.standing(faction) < 0;
+        let reputation = self.reputation.entry(faction).or_default();
+        *reputation = (*reputation + change).clamp(-MAX_REPUTATION, MAX_REPUTATION);
+
+        let hostile = self.standing(faction) < 0;
+        if hostile != was_hostile {
+            self.notify(Event::ReputationChanged { faction, hostile });
+        }
+    }
+
+    /// Adjust the player's reputation after they attack or kill someone
+    pub fn offend(&mut self, victim: EntityId, killed: bool) {
+        let faction = match self.faction(victim) {
+            Some(faction) if faction != Faction::Player => faction,
+            _ => return,
+        };
+
+        if !killed {
+            self.change_reputation(faction, -ATTACK_PENALTY);
+            return;
+        }
+
+        self.change_reputation(faction, -KILL_PENALTY);
+        for rival in Faction::ALL {
+            if rival != faction && rival.attitude(faction) < 0 {
+                self.change_reputation(rival, RIVAL_KILL_BONUS);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::action::Action;
+    use crate::ai::Ai;
+    use crate::ai::think;
+    use crate::health::Health;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::Faction;
+
+    #[test]
+    fn attitudes_are_symmetric() {
+        for a in Faction::ALL {
+            assert_eq!(a.attitude(a), 100);
+            for b in Faction::ALL {
+                assert_eq!(a.attitude(b), b.attitude(a));
+            }
+        }
+    }
+
+    #[test]
+    fn reputation_flips_hostility() {
+        let mut world = World::test([[Tile::Planks as u8; 16]; 16], [0, 0]);
+        let (player, villager, goblin) = (world.player, world.spawn(), world.spawn());
+        world.factions.insert(villager, Faction::Townsfolk);
+        world.factions.insert(goblin, Faction::Goblins);
+        assert!(!world.is_hostile(player, villager));
+        assert!(world.is_hostile(goblin, player));
+
+        world.offend(villager, false);
+        assert!(!world.is_hostile(player, villager));
+        world.offend(villager, false);
+        world.offend(villager, false);
+        assert!(world.is_hostile(villager, player));
+        assert!(world.log.lines(1)[0].text.contains("townsfolk are now hostile"));
+
+        // Killing orcs wins over the goblins, who hate them
+        let orc = world.spawn();
+        world.factions.insert(orc, Faction::Orcs);
+        for _ in 0..5 {
+            world.offend(orc, true);
+        }
+        assert!(!world.is_hostile(goblin, player));
+        assert!(world.is_hostile(goblin, orc));
+
+        let saved = serde_json::to_string(&world).unwrap();
+        let loaded: World = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.reputation, world.reputation);
+    }
+
+    #[test]
+    fn monsters_fight_each_other() {
+        let mut world = World::test([[Tile::Planks as u8; 16]; 16], [0, 0]);
+        let (goblin, orc) = (world.spawn(), world.spawn());
+        for (id, faction, position) in [(goblin, Faction::Goblins, [10, 10]), (orc, Faction::Orcs, [11, 10])] {
+            world.positions.insert(id, position);
+            world.factions.insert(id, faction);
+            world.healths.insert(id, Health::new(10));
+            world.ais.insert(id, Ai::new(position));
+        }
+
+        assert_eq!(think(&mut world, goblin), Action::Attack(orc));
+        world.factions.insert(orc, Faction::Goblins);
+        assert_ne!(think(&mut world, goblin), Action::Attack(orc));
+    }
+}
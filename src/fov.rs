@@ -0,0 +1,365 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use bytemuck::Pod;
+use bytemuck::Zeroable;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::chunk::ChunkMask;
+use crate::chunk::split_position;
+use crate::entity::Entity;
+use crate::world::World;
+
+pub const DEFAULT_FOV_RADIUS: i32 = 8;
+
+/// What the player can currently see, and everything they have seen before
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Visibility {
+    pub radius: i32,
+    #[serde(skip)]
+    pub visible: HashSet<[i32; 2]>,
+    /// Explored tiles, indexed in the same order as `World::chunks`
+    pub explored: Vec<ChunkMask>,
+    /// The origin and radius `visible` was computed from, or `None` if it needs recomputing
+    #[serde(skip)]
+    computed: Option<([i32; 2], i32)>,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Self {
+            radius: DEFAULT_FOV_RADIUS,
+            visible: HashSet::new(),
+            explored: vec![],
+            computed: None,
+        }
+    }
+}
+
+/// The visibility of a single chunk, laid out for upload alongside the chunk buffer
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+pub struct VisibilityMask {
+    pub visible: ChunkMask,
+    pub explored: ChunkMask,
+}
+
+impl Visibility {
+    pub fn is_visible(&self, position: [i32; 2]) -> bool {
+        self.visible.contains(&position)
+    }
+
+    /// Force the next update to recompute what's visible, such as after the map changes
+    pub fn invalidate(&mut self) {
+        self.computed = None;
+    }
+}
+
+impl World {
+    /// Recompute what the player can see and remember it as explored
+    ///
+    /// Returns whether anything was recomputed, which only happens if the player has moved, their
+    /// sight has changed or the visibility was invalidated.
+    pub fn update_visibility(&mut self) -> bool {
+        let origin = match self.positions.get(&self.player) {
+            Some(position) => *position,
+            None => return false,
+        };
+
+        let radius = self.sight_radius(self.player, self.visibility.radius);
+        if self.visibility.computed == Some((origin, radius)) {
+            return false;
+        }
+
+        let mut visible = HashSet::new();
+        compute_fov(
+            origin,
+            radius,
+            |position| self.tile(position).is_none_or(|tile| tile.is_opaque()),
+            |position| { visible.insert(position); },
+        );
+
+        self.visibility.explored.resize(self.chunks.len(), ChunkMask::default());
+        let mut explored = 0;
+        for position in &visible {
+            let (chunk_position, local_position) = split_position(*position);
+            if let Some(index) = self.chunk_index(chunk_position) {
+                let mask = &mut self.visibility.explored[index];
+                if !mask.get(local_position) {
+                    mask.set(local_position);
+                    explored += 1;
+                }
+            }
+        }
+
+        self.visibility.visible = visible;
+        self.visibility.computed = Some((origin, radius));
+        self.record_exploration(explored);
+        true
+    }
+
+    /// Whether one position can see another within a radius
+    pub fn can_see(&self, from: [i32; 2], to: [i32; 2], radius: i32) -> bool {
+        let [dx, dy] = [to[0] - from[0], to[1] - from[1]];
+        if dx * dx + dy * dy > radius * radius {
+            return false;
+        }
+
+        let mut seen = false;
+        compute_fov(
+            from,
+            radius,
+            |position| self.tile(position).is_none_or(|tile| tile.is_opaque()),
+            |position| seen |= position == to,
+        );
+
+        seen
+    }
+
+    /// Get the sprites of every entity on a tile the player can see
+    pub fn visible_sprites(&self) -> Vec<Entity> {
+        self.entities
+            .values()
+            .filter(|entity| {
+                let [x, y] = entity.position();
+                self.visibility.is_visible([x.round() as i32, y.round() as i32])
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Build a visibility mask for each chunk, in the same order as `chunks`
+    pub fn visibility_masks(&self) -> Vec<VisibilityMask> {
+        let mut masks: Vec<VisibilityMask> = (0..self.chunks.len())
+            .map(|index| VisibilityMask {
+                visible: ChunkMask::default(),
+                explored: self.visibility.explored.get(index).copied().unwrap_or_default(),
+            })
+            .collect();
+
+        for position in &self.visibility.visible {
+            let (chunk_position, local_position) = split_position(*position);
+            if let Some(index) = self.chunk_index(chunk_position) {
+                masks[index].visible.set(local_position);
+            }
+        }
+
+        masks
+    }
+}
+
+/// A slope expressed as a fraction so that comparisons are exact
+#[derive(Copy, Clone, Debug)]
+struct Slope {
+    numerator: i32,
+    denominator: i32,
+}
+
+impl Slope {
+    fn new(numerator: i32, denominator: i32) -> Self {
+        Self { numerator, denominator }
+    }
+
+    /// Compare `depth * self` against a column
+    fn cmp_scaled(self, depth: i32, column: i32) -> Ordering {
+        (depth * self.numerator).cmp(&(column * self.denominator))
+    }
+}
+
+/// The cardinal direction a quadrant faces, with `depth` moving away from the origin
+/// and `column` moving across it
+#[derive(Copy, Clone, Debug)]
+enum Quadrant {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Quadrant {
+    fn transform(self, [x, y]: [i32; 2], depth: i32, column: i32) -> [i32; 2] {
+        match self {
+            Quadrant::North => [x + column, y - depth],
+            Quadrant::East => [x + depth, y + column],
+            Quadrant::South => [x + column, y + depth],
+            Quadrant::West => [x - depth, y + column],
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Row {
+    depth: i32,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    fn columns(&self) -> std::ops::RangeInclusive<i32> {
+        // Round the start up and the end down when a slope falls exactly between tiles
+        let round_ties_up = |slope: Slope| {
+            (2 * self.depth * slope.numerator + slope.denominator).div_euclid(2 * slope.denominator)
+        };
+        let round_ties_down = |slope: Slope| {
+            -(-2 * self.depth * slope.numerator + slope.denominator).div_euclid(2 * slope.denominator)
+        };
+
+        round_ties_up(self.start)..=round_ties_down(self.end)
+    }
+
+    fn next(&self) -> Self {
+        Self { depth: self.depth + 1, ..*self }
+    }
+
+    /// Whether a floor tile in this row can see the origin as well as be seen by it
+    fn is_symmetric(&self, column: i32) -> bool {
+        self.start.cmp_scaled(self.depth, column) != Ordering::Greater
+            && self.end.cmp_scaled(self.depth, column) != Ordering::Less
+    }
+}
+
+/// Compute the tiles visible from an origin with symmetric shadowcasting
+///
+/// Every tile within `radius` that is visible is passed to `reveal`, which may be called
+/// more than once for the same tile.
+pub fn compute_fov<O, R>(origin: [i32; 2], radius: i32, is_opaque: O, mut reveal: R)
+where
+    O: Fn([i32; 2]) -> bool,
+    R: FnMut([i32; 2]),
+{
+    reveal(origin);
+
+    for quadrant in [Quadrant::North, Quadrant::East, Quadrant::South, Quadrant::West] {
+        let row = Row { depth: 1, start: Slope::new(-1, 1), end: Slope::new(1, 1) };
+        scan(origin, radius, quadrant, row, &is_opaque, &mut reveal);
+    }
+}
+
+fn scan<O, R>(origin: [i32; 2], radius: i32, quadrant: Quadrant, mut row: Row, is_opaque: &O, reveal: &mut R)
+where
+    O: Fn([i32; 2]) -> bool,
+    R: FnMut([i32; 2]),
+{
+    if row.depth > radius {
+        return;
+    }
+
+    let mut previous_opaque = None;
+    for column in row.columns() {
+        let position = quadrant.transform(origin, row.depth, column);
+        let opaque = is_opaque(position);
+        let in_range = row.depth * row.depth + column * column <= radius * radius;
+
+        if in_range && (opaque || row.is_symmetric(column)) {
+            reveal(position);
+        }
+
+        match (previous_opaque, opaque) {
+            (Some(true), false) => row.start = Slope::new(2 * column - 1, 2 * row.depth),
+            (Some(false), true) => {
+                let mut next = row.next();
+                next.end = Slope::new(2 * column - 1, 2 * row.depth);
+                scan(origin, radius, quadrant, next, is_opaque, reveal);
+            }
+            _ => (),
+        }
+
+        previous_opaque = Some(opaque);
+    }
+
+    if previous_opaque == Some(false) {
+        scan(origin, radius, quadrant, row.next(), is_opaque, reveal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::compute_fov;
+
+    fn fov(map: &[&str], origin: [i32; 2], radius: i32) -> HashSet<[i32; 2]> {
+        let is_opaque = |[x, y]: [i32; 2]| {
+            map.get(y as usize)
+                .and_then(|row| row.as_bytes().get(x as usize))
+                .is_none_or(|tile| *tile == b'#')
+        };
+
+        let mut visible = HashSet::new();
+        compute_fov(origin, radius, is_opaque, |position| { visible.insert(position); });
+        visible
+    }
+
+    #[test]
+    fn symmetry() {
+        let map = [
+            "##########",
+            "#....#...#",
+            "#.#......#",
+            "#...##.#.#",
+            "#.#....#.#",
+            "#....#...#",
+            "#.##...#.#",
+            "##########",
+        ];
+
+        let floors: Vec<[i32; 2]> = (0..map.len() as i32)
+            .flat_map(|y| (0..map[0].len() as i32).map(move |x| [x, y]))
+            .filter(|[x, y]| map[*y as usize].as_bytes()[*x as usize] == b'.')
+            .collect();
+
+        for a in &floors {
+            let from_a = fov(&map, *a, 20);
+            for b in &floors {
+                assert_eq!(from_a.contains(b), fov(&map, *b, 20).contains(a), "{a:?} and {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn wall_corners() {
+        let map = [
+            "#######",
+            "#.....#",
+            "#.....#",
+            "#.....#",
+            "#######",
+        ];
+
+        let visible = fov(&map, [3, 2], 20);
+        for y in 0..map.len() as i32 {
+            for x in 0..map[0].len() as i32 {
+                assert!(visible.contains(&[x, y]), "{x}, {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn pillar_casts_shadow() {
+        let map = [
+            ".......",
+            ".......",
+            "...#...",
+            ".......",
+            ".......",
+        ];
+
+        let visible = fov(&map, [3, 0], 20);
+        assert!(visible.contains(&[3, 2]));
+        assert!(!visible.contains(&[3, 3]));
+        assert!(!visible.contains(&[3, 4]));
+        assert!(visible.contains(&[0, 4]));
+    }
+
+    #[test]
+    fn radius() {
+        let map = [".........."; 10];
+
+        let visible = fov(&map, [0, 0], 3);
+        assert!(visible.contains(&[3, 0]));
+        assert!(visible.contains(&[2, 2]));
+        assert!(!visible.contains(&[4, 0]));
+        assert!(!visible.contains(&[3, 3]));
+    }
+}
@@ -54,10 +54,9 @@ use wgpu::util::BufferInitDescriptor;
 use wgpu::util::DeviceExt;
 
 use crate::chunk::CHUNK_CLEAR_COLOR;
-use crate::chunk::CHUNK_SIZE;
 use crate::chunk::Chunk;
-use crate::ecs::Resolution;
 use crate::error::Error;
+use crate::fov::VisibilityMask;
 use crate::material::Material;
 use crate::tile::TILE_SIZE;
 use crate::tile::Tile;
@@ -79,6 +78,7 @@ pub struct ChunkRenderer {
     pipeline: RenderPipeline,
     locals: Buffer,
     chunks: Buffer,
+    visibility: Buffer,
     tiles: Buffer,
     materials: Texture,
     materials_view: TextureView,
@@ -86,13 +86,14 @@ pub struct ChunkRenderer {
 }
 
 impl ChunkRenderer {
-    pub fn new(rc: &RenderingContext, globals: &Buffer, resolution: Resolution) -> Result<Self, Error> {
+    pub fn new(rc: &RenderingContext, globals: &Buffer, chunk_count: usize) -> Result<Self, Error> {
         // Load this now to test for compilation errors
         let shader = rc.device.create_shader_module(&include_wgsl!("shaders/chunk.wgsl"));
 
-        let chunks_per_row = (resolution.width as f32 / (CHUNK_SIZE * TILE_SIZE) as f32).ceil() as u64 + 1;
-        let chunks_per_column = (resolution.height as f32 / (CHUNK_SIZE * TILE_SIZE) as f32).ceil() as u64 + 1;
-        let chunk_data_size = std::mem::size_of::<Chunk>() as u64 * (chunks_per_row + 2) * (chunks_per_column + 2);
+        // Chunks are laid out exactly as they are in the world, so the shader indexes them the same way
+        let chunk_count = chunk_count.max(1) as u64;
+        let chunk_data_size = std::mem::size_of::<Chunk>() as u64 * chunk_count;
+        let visibility_data_size = std::mem::size_of::<VisibilityMask>() as u64 * chunk_count;
 
         let bind_group_layout = rc.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("chunk_renderer::bind_group_layout"),
@@ -147,6 +148,16 @@ impl ChunkRenderer {
                     },
                     count: None,
                 },
+                BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(visibility_data_size),
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -205,6 +216,13 @@ impl ChunkRenderer {
             mapped_at_creation: false,
         });
 
+        let visibility = rc.device.create_buffer(&BufferDescriptor {
+            label: Some("chunk_renderer::visibility"),
+            size: visibility_data_size,
+            usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
         let tiles = rc.device.create_buffer_init(&BufferInitDescriptor {
             label: Some("chunk_renderer::tiles"),
             contents: bytemuck::cast_slice(&Tile::tile_data()),
@@ -274,6 +292,10 @@ impl ChunkRenderer {
                     binding: 4,
                     resource: BindingResource::TextureView(&materials_view),
                 },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: visibility.as_entire_binding(),
+                },
             ],
         });
 
@@ -284,6 +306,7 @@ impl ChunkRenderer {
             pipeline,
             locals,
             chunks,
+            visibility,
             tiles,
             materials,
             materials_view,
@@ -295,6 +318,16 @@ impl ChunkRenderer {
         rc.queue.write_buffer(&self.chunks, 0, bytemuck::cast_slice(chunks));
     }
 
+    /// Overwrite a single chunk, such as after one of its tiles changes
+    pub fn write_chunk(&self, rc: &RenderingContext, index: usize, chunk: &Chunk) {
+        let offset = std::mem::size_of::<Chunk>() as u64 * index as u64;
+        rc.queue.write_buffer(&self.chunks, offset, bytemuck::bytes_of(chunk));
+    }
+
+    pub fn write_visibility(&self, rc: &RenderingContext, visibility: &[VisibilityMask]) {
+        rc.queue.write_buffer(&self.visibility, 0, bytemuck::cast_slice(visibility));
+    }
+
     pub fn render(
         &self,
         rc: &RenderingContext,
@@ -1,19 +1,73 @@
+use std::num::NonZeroU32;
+
+use rendering_util::RenderingContext;
+use wgpu::BindGroup;
+use wgpu::BindGroupDescriptor;
+use wgpu::BindGroupEntry;
+use wgpu::BindGroupLayout;
+use wgpu::BindGroupLayoutDescriptor;
+use wgpu::BindGroupLayoutEntry;
+use wgpu::BindingResource;
+use wgpu::BindingType;
+use wgpu::BlendState;
+use wgpu::Buffer;
+use wgpu::BufferBinding;
+use wgpu::BufferBindingType;
+use wgpu::BufferDescriptor;
+use wgpu::BufferSize;
+use wgpu::BufferUsages;
+use wgpu::ColorTargetState;
+use wgpu::ColorWrites;
+use wgpu::CommandEncoderDescriptor;
+use wgpu::Extent3d;
+use wgpu::FragmentState;
+use wgpu::FrontFace;
+use wgpu::LoadOp;
+use wgpu::MultisampleState;
+use wgpu::Operations;
+use wgpu::PipelineLayoutDescriptor;
+use wgpu::PolygonMode;
+use wgpu::PrimitiveState;
+use wgpu::PrimitiveTopology;
+use wgpu::RenderPassColorAttachment;
+use wgpu::RenderPassDescriptor;
+use wgpu::RenderPipeline;
+use wgpu::RenderPipelineDescriptor;
+use wgpu::ShaderStages;
+use wgpu::TextureAspect;
+use wgpu::TextureDescriptor;
+use wgpu::TextureDimension;
+use wgpu::TextureSampleType;
+use wgpu::TextureUsages;
+use wgpu::TextureView;
+use wgpu::TextureViewDescriptor;
+use wgpu::TextureViewDimension;
+use wgpu::VertexState;
+use wgpu::include_wgsl;
+
+use crate::ecs::Resolution;
+use crate::entity::Entity;
+use crate::error::Error;
+use crate::tile::TILE_SIZE;
 
 const MIN_ENTITY_COUNT: u64 = 16;
 
-struct EntityRenderer {
+pub struct EntityRenderer {
+    bind_group_layout: BindGroupLayout,
     pipeline: RenderPipeline,
     locals: Buffer,
+    capacity: u64,
+    atlas_view: TextureView,
     bind_group: BindGroup,
-    count: u32,
+    positions: Vec<[f32; 2]>,
 }
 
 impl EntityRenderer {
-    pub fn new(rc: &RenderingContext, resolution: Resolution) -> Self {
-        let shader = rc.device.create_shader_module(&include_wgsl!("shaders/fs_entity.wgsl"));
+    pub fn new(rc: &RenderingContext) -> Result<Self, Error> {
+        let shader = rc.device.create_shader_module(&include_wgsl!("shaders/entity.wgsl"));
 
         let bind_group_layout = rc.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("entity_bind_group_layout"),
+            label: Some("entity_renderer::bind_group_layout"),
             entries: &[
                 BindGroupLayoutEntry {
                     binding: 0,
@@ -21,7 +75,7 @@ impl EntityRenderer {
                     ty: BindingType::Buffer {
                         ty: BufferBindingType::Uniform,
                         has_dynamic_offset: true,
-                        min_binding_size: BufferSize::new(0),
+                        min_binding_size: BufferSize::new(std::mem::size_of::<Entity>() as _),
                     },
                     count: None,
                 },
@@ -39,16 +93,16 @@ impl EntityRenderer {
         });
 
         let pipeline_layout = rc.device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("entity_pipeline_layout"),
-            bind_group_layouts: &[&entity_bind_group_layout],
+            label: Some("entity_renderer::pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
             push_constant_ranges: &[],
         });
 
         let pipeline = rc.device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("entity_pipeline"),
-            layout: Some(&entity_pipeline_layout),
+            label: Some("entity_renderer::pipeline"),
+            layout: Some(&pipeline_layout),
             vertex: VertexState {
-                module: &vs_quad,
+                module: &shader,
                 entry_point: "vs_main",
                 buffers: &[],
             },
@@ -68,89 +122,85 @@ impl EntityRenderer {
                 alpha_to_coverage_enabled: false,
             },
             fragment: Some(FragmentState {
-                module: &fs_entity,
+                module: &shader,
                 entry_point: "fs_main",
                 targets: &[ColorTargetState {
-                    format: TEXTURE_FORMAT,
+                    format: rc.surface_format(),
                     blend: Some(BlendState::ALPHA_BLENDING),
                     write_mask: ColorWrites::ALL,
                 }],
             }),
             multiview: None,
         });
-    
-        let entity_locals = rc.device.create_buffer(&BufferDescriptor {
-            label: Some("entity_locals"),
-            size: std::mem::size_of::<Entity>() as u64 * MIN_ENTITY_COUNT,
-            usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
-            mapped_at_creation: false,
-        });
 
-        let (entity_atlas_data, entity_atlas_size) = entities();
-        let entity_atlas = rc.device.create_texture(&TextureDescriptor {
-            label: Some("entity_atlas"),
-            size: entity_atlas_size,
+        let locals = create_locals(rc, MIN_ENTITY_COUNT);
+
+        let (atlas_data, atlas_size) = atlas_data()?;
+        let atlas = rc.device.create_texture(&TextureDescriptor {
+            label: Some("entity_renderer::atlas"),
+            size: atlas_size,
             mip_level_count: 1,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: TEXTURE_FORMAT,
+            format: rc.surface_format(),
             usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
         });
 
         rc.queue.write_texture(
             wgpu::ImageCopyTexture {
-                texture: &entity_atlas,
+                texture: &atlas,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &entity_atlas_data,
+            &atlas_data,
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: NonZeroU32::new(entity_atlas_size.width * TEXTURE_FORMAT.describe().block_size as u32),
+                bytes_per_row: NonZeroU32::new(atlas_size.width * rc.surface_format().describe().block_size as u32),
                 rows_per_image: None,
             },
-            entity_atlas_size,
+            atlas_size,
         );
 
-        let entity_atlas_view = entity_atlas.create_view(&TextureViewDescriptor {
-            label: Some("entity_atlas_view"),
-            format: Some(TEXTURE_FORMAT),
+        let atlas_view = atlas.create_view(&TextureViewDescriptor {
+            label: Some("entity_renderer::atlas_view"),
+            format: Some(rc.surface_format()),
             dimension: Some(TextureViewDimension::D2),
             aspect: TextureAspect::All,
             ..Default::default()
         });
 
-        let entity_bind_group = rc.device.create_bind_group(&BindGroupDescriptor {
-            label: Some("entity_bind_group"),
-            layout: &entity_bind_group_layout,
-            entries: &[
-                BindGroupEntry {
-                    binding: 0,
-                    resource: BindingResource::Buffer(BufferBinding {
-                        buffer: &entity_locals,
-                        offset: 0,
-                        size: BufferSize::new(std::mem::size_of::<Entity>() as _),
-                    }),
-                },
-                BindGroupEntry {
-                    binding: 1,
-                    resource: BindingResource::TextureView(&entity_atlas_view),
-                },
-            ],
-        });
+        let bind_group = create_bind_group(rc, &bind_group_layout, &locals, &atlas_view);
+
+        Ok(Self {
+            bind_group_layout,
+            pipeline,
+            locals,
+            capacity: MIN_ENTITY_COUNT,
+            atlas_view,
+            bind_group,
+            positions: vec![],
+        })
     }
 
     pub fn write_entities(&mut self, rc: &RenderingContext, entities: &[Entity]) {
-        self.entity_count = entities.len() as _;
+        // Grow the locals buffer when there are more entities than will fit
+        if entities.len() as u64 > self.capacity {
+            self.capacity = (entities.len() as u64).next_power_of_two();
+            self.locals = create_locals(rc, self.capacity);
+            self.bind_group = create_bind_group(rc, &self.bind_group_layout, &self.locals, &self.atlas_view);
+        }
 
+        self.positions = entities.iter().map(|entity| entity.position()).collect();
+
+        // Entity is padded out to the uniform offset alignment, so it can't be cast with bytemuck
         rc.queue.write_buffer(
-            &self.entity_locals,
+            &self.locals,
             0,
             unsafe {
                 std::slice::from_raw_parts(
                     entities.as_ptr() as *const u8,
-                    entities.len() * std::mem::size_of::<Entity>(),
+                    std::mem::size_of_val(entities),
                 )
             },
         )
@@ -160,13 +210,14 @@ impl EntityRenderer {
         &self,
         rc: &RenderingContext,
         surface_view: &TextureView,
+        resolution: Resolution,
     ) {
         // Build our command encoder
         let mut command_encoder = rc.device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some("command_encoder"),
         });
 
-        // Render chunks!
+        // Render entities on top of the chunks
         {
             let mut render_pass = command_encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("entity_renderer::render_pass"),
@@ -174,17 +225,26 @@ impl EntityRenderer {
                     view: surface_view,
                     resolve_target: None,
                     ops: Operations {
-                        load: LoadOp::Clear(CHUNK_CLEAR_COLOR),
+                        load: LoadOp::Load,
                         store: true,
                     },
                 }],
                 depth_stencil_attachment: None,
             });
 
-            render_pass.set_pipeline(&self.entity_pipeline);
-            for i in 0..self.entity_count {
-                render_pass.set_scissor_rect(0, 0, TILE_SIZE, TILE_SIZE);
-                render_pass.set_bind_group(0, &self.entity_bind_group, &[i * std::mem::size_of::<Entity>() as u32]);
+            render_pass.set_pipeline(&self.pipeline);
+            for (i, position) in self.positions.iter().enumerate() {
+                let x = position[0] * TILE_SIZE as f32;
+                let y = position[1] * TILE_SIZE as f32;
+                if x < 0.0 || y < 0.0 || x as u32 >= resolution.width || y as u32 >= resolution.height {
+                    continue;
+                }
+
+                let (x, y) = (x as u32, y as u32);
+                let width = TILE_SIZE.min(resolution.width - x);
+                let height = TILE_SIZE.min(resolution.height - y);
+                render_pass.set_scissor_rect(x, y, width, height);
+                render_pass.set_bind_group(0, &self.bind_group, &[(i * std::mem::size_of::<Entity>()) as u32]);
                 render_pass.draw(0..4, 0..1);
             }
         }
@@ -194,13 +254,45 @@ impl EntityRenderer {
     }
 }
 
-fn entities() -> (Vec<u8>, Extent3d) {
-    let bytes = include_bytes!("textures/entities.gif");
-    let decoder = GifDecoder::new(&bytes[..]).unwrap();
-    let dimensions = decoder.dimensions();
+fn create_locals(rc: &RenderingContext, capacity: u64) -> Buffer {
+    rc.device.create_buffer(&BufferDescriptor {
+        label: Some("entity_renderer::locals"),
+        size: std::mem::size_of::<Entity>() as u64 * capacity,
+        usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_bind_group(
+    rc: &RenderingContext,
+    layout: &BindGroupLayout,
+    locals: &Buffer,
+    atlas_view: &TextureView,
+) -> BindGroup {
+    rc.device.create_bind_group(&BindGroupDescriptor {
+        label: Some("entity_renderer::bind_group"),
+        layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: locals,
+                    offset: 0,
+                    size: BufferSize::new(std::mem::size_of::<Entity>() as _),
+                }),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::TextureView(atlas_view),
+            },
+        ],
+    })
+}
 
-    let mut buf = vec![0; decoder.total_bytes() as usize];
-    decoder.read_image(&mut buf).unwrap();
+fn atlas_data() -> Result<(Vec<u8>, Extent3d), Error> {
+    let data = std::fs::read("./textures/entities.gif")?;
+    let image = image::load_from_memory(&data)?.into_rgba8();
+    let (width, height) = image.dimensions();
 
-    (buf, Extent3d { width: dimensions.0, height: dimensions.1, depth_or_array_layers: 1 })
+    Ok((image.into_raw(), Extent3d { width, height, depth_or_array_layers: 1 }))
 }
@@ -1,4 +1,6 @@
 mod chunk_renderer;
+mod entity_renderer;
+mod text_renderer;
 
 use bytemuck::Pod;
 use bytemuck::Zeroable;
@@ -17,32 +19,46 @@ use winit::window::Window;
 
 use crate::chunk::Chunk;
 use crate::ecs::Resolution;
+use crate::entity::Entity;
 use crate::error::Error;
+use crate::fov::VisibilityMask;
 
 use self::chunk_renderer::ChunkRenderer;
+use self::entity_renderer::EntityRenderer;
+use self::text_renderer::TextRenderer;
+
+pub use self::text_renderer::HUD_ROWS;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 struct Globals {
     resolution: [u32; 2],
+    /// The number of chunks in each row of the world, shared with `World::chunk_index`
+    chunks_per_row: u32,
+    _padding: u32,
 }
 
 impl Globals {
-    fn new(resolution: Resolution) -> Self {
-        Self { resolution: [resolution.width, resolution.height] }
+    fn new(resolution: Resolution, chunks_per_row: u32) -> Self {
+        Self { resolution: [resolution.width, resolution.height], chunks_per_row, _padding: 0 }
     }
 }
 
 pub struct Graphics {
     rendering_context: RenderingContext,
     globals: Buffer,
+    chunks_per_row: u32,
     chunk_renderer: ChunkRenderer,
+    entity_renderer: EntityRenderer,
+    text_renderer: TextRenderer,
 }
 
 impl Graphics {
     pub async fn new(
         window: &Window,
         resolution: Resolution,
+        chunks_per_row: u32,
+        chunk_count: usize,
     ) -> Result<Self, Error> {
         let width = resolution.width;
         let height = resolution.height;
@@ -52,17 +68,22 @@ impl Graphics {
         let globals = rc.device.create_buffer_init(&BufferInitDescriptor {
             label: Some("globals"),
             contents: bytemuck::bytes_of(
-                &Globals::new(resolution)
+                &Globals::new(resolution, chunks_per_row)
             ),
             usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
         });
 
-        let chunk_renderer = ChunkRenderer::new(&rc, &globals, resolution)?;
+        let chunk_renderer = ChunkRenderer::new(&rc, &globals, chunk_count)?;
+        let entity_renderer = EntityRenderer::new(&rc)?;
+        let text_renderer = TextRenderer::new(&rc)?;
 
         Ok(Self {
             rendering_context: rc,
             globals,
+            chunks_per_row,
             chunk_renderer,
+            entity_renderer,
+            text_renderer,
         })
     }
 
@@ -70,6 +91,22 @@ impl Graphics {
         self.chunk_renderer.write_chunks(&self.rendering_context, chunks);
     }
 
+    pub fn write_chunk(&self, index: usize, chunk: &Chunk) {
+        self.chunk_renderer.write_chunk(&self.rendering_context, index, chunk);
+    }
+
+    pub fn write_visibility(&self, visibility: &[VisibilityMask]) {
+        self.chunk_renderer.write_visibility(&self.rendering_context, visibility);
+    }
+
+    pub fn write_entities(&mut self, entities: &[Entity]) {
+        self.entity_renderer.write_entities(&self.rendering_context, entities);
+    }
+
+    pub fn write_hud(&self, lines: &[(String, u32)]) {
+        self.text_renderer.write_lines(&self.rendering_context, lines);
+    }
+
     pub fn render(&mut self, resolution: Resolution) -> Result<(), Error> {
         let rc = &self.rendering_context;
         let width = resolution.width;
@@ -79,12 +116,14 @@ impl Graphics {
         rc.queue.write_buffer(
             &self.globals,
             0,
-            bytemuck::bytes_of(&Globals::new(resolution),
+            bytemuck::bytes_of(&Globals::new(resolution, self.chunks_per_row),
         ));
 
         // Do our rendering
         self.rendering_context.render(width, height, |rc, surface_view| {
             self.chunk_renderer.render(rc, surface_view);
+            self.entity_renderer.render(rc, surface_view, resolution);
+            self.text_renderer.render(rc, surface_view, resolution);
         })?;
 
         Ok(())
@@ -2,9 +2,13 @@ let BITS_PER_BYTE: u32 = 8u;
 let SIZEOF_U32: u32 = 4u;
 let TILE_SIZE: u32 = 16u;
 let CHUNK_SIZE: u32 = 16u;
+let MASK_WORDS: u32 = 8u;
+let EXPLORED_TINT: f32 = 0.2;
+let EXPLORED_BRIGHTNESS: f32 = 0.4;
 
 struct Globals {
     resolution: vec2<u32>;
+    chunks_per_row: u32;
 };
 
 struct Locals {
@@ -15,6 +19,10 @@ struct ChunkData {
     data: array<u32>;
 };
 
+struct VisibilityData {
+    data: array<u32>;
+};
+
 struct Tile {
     material: i32;
     primary_color: u32;
@@ -35,6 +43,8 @@ var<storage, read> chunk_data: ChunkData;
 var<storage, read> tile_data: TileData;
 [[group(0), binding(4)]]
 var materials: texture_2d_array<f32>;
+[[group(0), binding(5)]]
+var<storage, read> visibility_data: VisibilityData;
 
 [[stage(vertex)]]
 fn vs_main([[builtin(vertex_index)]] vertex_index: u32) -> [[builtin(position)]] vec4<f32> {
@@ -44,14 +54,28 @@ fn vs_main([[builtin(vertex_index)]] vertex_index: u32) -> [[builtin(position)]]
     return vec4<f32>(x, y, 0.0, 1.0);
 }
 
-fn get_tile(x: u32, y: u32) -> u32 {
+fn get_tile_index(x: u32, y: u32) -> u32 {
     let pixels_per_chunk_axis = TILE_SIZE * CHUNK_SIZE;
     let tiles_per_chunk = CHUNK_SIZE * CHUNK_SIZE;
-    let chunks_per_row = u32(ceil(f32(globals.resolution.x) / f32(pixels_per_chunk_axis) + 3.0));
+    let chunks_per_row = globals.chunks_per_row;
     let x = x / TILE_SIZE % TILE_SIZE + x / pixels_per_chunk_axis * tiles_per_chunk;
     let y = y / TILE_SIZE % TILE_SIZE * CHUNK_SIZE + y / pixels_per_chunk_axis * tiles_per_chunk * chunks_per_row;
-    let tile = chunk_data.data[(x + y) / 4u];
-    return (tile >> ((x % 4u) * BITS_PER_BYTE)) & 0xffu;
+    return x + y;
+}
+
+fn get_tile(x: u32, y: u32) -> u32 {
+    let index = get_tile_index(x, y);
+    let tile = chunk_data.data[index / 4u];
+    return (tile >> ((index % 4u) * BITS_PER_BYTE)) & 0xffu;
+}
+
+// Each chunk has a visible mask followed by an explored mask, one bit per tile
+fn get_visibility_bit(index: u32, mask: u32) -> bool {
+    let tiles_per_chunk = CHUNK_SIZE * CHUNK_SIZE;
+    let chunk = index / tiles_per_chunk;
+    let bit = index % tiles_per_chunk;
+    let word = visibility_data.data[(chunk * 2u + mask) * MASK_WORDS + bit / 32u];
+    return ((word >> (bit % 32u)) & 1u) != 0u;
 }
 
 [[stage(fragment)]]
@@ -60,6 +84,12 @@ fn fs_main([[builtin(position)]] position: vec4<f32>) -> [[location(0)]] vec4<f3
     let buffer_size = arrayLength(&chunk_data.data);
     let position = vec2<u32>(position.xy);
 
+    // Anything right of the last column or below the last row is outside the world
+    let chunk_x = position.x / (TILE_SIZE * CHUNK_SIZE);
+    if (chunk_x >= globals.chunks_per_row || get_tile_index(position.x, position.y) / 4u >= buffer_size) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
     let tile = get_tile(position.x, position.y);
     let up = u32(tile == get_tile(position.x, position.y - TILE_SIZE)) << 1u;
     let down = u32(tile == get_tile(position.x, position.y + TILE_SIZE));
@@ -74,7 +104,19 @@ fn fs_main([[builtin(position)]] position: vec4<f32>) -> [[location(0)]] vec4<f3
     let primary_color = unpack4x8unorm(tile_data.primary_color);
     let secondary_color = unpack4x8unorm(tile_data.secondary_color);
 
+    let index = get_tile_index(position.x, position.y);
+    if (!get_visibility_bit(index, 1u)) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
     var color = textureLoad(materials, vec2<i32>(position.xy % TILE_SIZE) + sprite_offset, material, 0);
     color = primary_color * color + secondary_color * vec4<f32>(1.0 - color.rgb, color.a);
+
+    // Remembered tiles are drawn dim and mostly grey
+    if (!get_visibility_bit(index, 0u)) {
+        let luminance = dot(color.rgb, vec3<f32>(0.299, 0.587, 0.114));
+        color = vec4<f32>(mix(vec3<f32>(luminance), color.rgb, EXPLORED_TINT) * EXPLORED_BRIGHTNESS, color.a);
+    }
+
     return color;
 }
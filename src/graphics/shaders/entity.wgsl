@@ -13,6 +13,15 @@ var<uniform> locals: Locals;
 [[group(0), binding(1)]]
 var entity_atlas: texture_2d<f32>;
 
+// Entities are drawn as a full screen quad scissored down to the entity's tile
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] vertex_index: u32) -> [[builtin(position)]] vec4<f32> {
+    let vertex_index = i32(vertex_index);
+    let x = f32(vertex_index % 2 * 2 - 1);
+    let y = f32(vertex_index / 2 * 2 - 1);
+    return vec4<f32>(x, y, 0.0, 1.0);
+}
+
 [[stage(fragment)]]
 fn fs_main([[builtin(position)]] position: vec4<f32>) -> [[location(0)]] vec4<f32> {
     let tile_position = vec2<i32>(locals.atlas_position * TILE_SIZE);
@@ -0,0 +1,58 @@
+let GLYPH_WIDTH: u32 = 8u;
+let GLYPH_HEIGHT: u32 = 16u;
+let ATLAS_COLUMNS: u32 = 16u;
+let FIRST_CHARACTER: u32 = 32u;
+let LAST_CHARACTER: u32 = 127u;
+let MAX_COLUMNS: u32 = 160u;
+let BACKGROUND_ALPHA: f32 = 0.7;
+
+struct Locals {
+    origin: vec2<u32>;
+    columns: u32;
+    rows: u32;
+};
+
+// Every cell is a character followed by its color
+struct Cells {
+    data: array<u32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> locals: Locals;
+[[group(0), binding(1)]]
+var<storage, read> cells: Cells;
+[[group(0), binding(2)]]
+var font_atlas: texture_2d<f32>;
+
+// Text is drawn as a full screen quad scissored down to the panel
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] vertex_index: u32) -> [[builtin(position)]] vec4<f32> {
+    let vertex_index = i32(vertex_index);
+    let x = f32(vertex_index % 2 * 2 - 1);
+    let y = f32(vertex_index / 2 * 2 - 1);
+    return vec4<f32>(x, y, 0.0, 1.0);
+}
+
+[[stage(fragment)]]
+fn fs_main([[builtin(position)]] position: vec4<f32>) -> [[location(0)]] vec4<f32> {
+    let background = vec4<f32>(0.0, 0.0, 0.0, BACKGROUND_ALPHA);
+    let pixel = vec2<u32>(position.xy) - locals.origin;
+    let cell = vec2<u32>(pixel.x / GLYPH_WIDTH, pixel.y / GLYPH_HEIGHT);
+    if (cell.x >= locals.columns || cell.y >= locals.rows) {
+        return background;
+    }
+
+    let index = (cell.y * MAX_COLUMNS + cell.x) * 2u;
+    let character = cells.data[index];
+    if (character < FIRST_CHARACTER || character > LAST_CHARACTER) {
+        return background;
+    }
+
+    let glyph = character - FIRST_CHARACTER;
+    let glyph_position = vec2<u32>(glyph % ATLAS_COLUMNS * GLYPH_WIDTH, glyph / ATLAS_COLUMNS * GLYPH_HEIGHT);
+    let pixel_position = vec2<u32>(pixel.x % GLYPH_WIDTH, pixel.y % GLYPH_HEIGHT);
+    let coverage = textureLoad(font_atlas, vec2<i32>(glyph_position + pixel_position), 0).a;
+
+    let color = unpack4x8unorm(cells.data[index + 1u]).rgb;
+    return vec4<f32>(color * coverage, mix(BACKGROUND_ALPHA, 1.0, coverage));
+}
@@ -0,0 +1,317 @@
+use std::num::NonZeroU32;
+
+use bytemuck::Pod;
+use bytemuck::Zeroable;
+use rendering_util::RenderingContext;
+use wgpu::BindGroup;
+use wgpu::BindGroupDescriptor;
+use wgpu::BindGroupEntry;
+use wgpu::BindGroupLayoutDescriptor;
+use wgpu::BindGroupLayoutEntry;
+use wgpu::BindingResource;
+use wgpu::BindingType;
+use wgpu::BlendState;
+use wgpu::Buffer;
+use wgpu::BufferBinding;
+use wgpu::BufferBindingType;
+use wgpu::BufferDescriptor;
+use wgpu::BufferSize;
+use wgpu::BufferUsages;
+use wgpu::ColorTargetState;
+use wgpu::ColorWrites;
+use wgpu::CommandEncoderDescriptor;
+use wgpu::Extent3d;
+use wgpu::FragmentState;
+use wgpu::FrontFace;
+use wgpu::LoadOp;
+use wgpu::MultisampleState;
+use wgpu::Operations;
+use wgpu::PipelineLayoutDescriptor;
+use wgpu::PolygonMode;
+use wgpu::PrimitiveState;
+use wgpu::PrimitiveTopology;
+use wgpu::RenderPassColorAttachment;
+use wgpu::RenderPassDescriptor;
+use wgpu::RenderPipeline;
+use wgpu::RenderPipelineDescriptor;
+use wgpu::ShaderStages;
+use wgpu::TextureAspect;
+use wgpu::TextureDescriptor;
+use wgpu::TextureDimension;
+use wgpu::TextureSampleType;
+use wgpu::TextureUsages;
+use wgpu::TextureView;
+use wgpu::TextureViewDescriptor;
+use wgpu::TextureViewDimension;
+use wgpu::VertexState;
+use wgpu::include_wgsl;
+
+use crate::ecs::Resolution;
+use crate::error::Error;
+
+pub const GLYPH_WIDTH: u32 = 8;
+pub const GLYPH_HEIGHT: u32 = 16;
+/// The widest line of text that can be drawn, matching `MAX_COLUMNS` in the shader
+pub const MAX_COLUMNS: usize = 160;
+/// The number of lines of text in the HUD panel
+pub const HUD_ROWS: usize = 6;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Locals {
+    origin: [u32; 2],
+    columns: u32,
+    rows: u32,
+}
+
+/// A single character cell, with zero meaning the cell is empty
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+struct Cell {
+    character: u32,
+    color: u32,
+}
+
+pub struct TextRenderer {
+    pipeline: RenderPipeline,
+    locals: Buffer,
+    cells: Buffer,
+    bind_group: BindGroup,
+}
+
+impl TextRenderer {
+    pub fn new(rc: &RenderingContext) -> Result<Self, Error> {
+        let shader = rc.device.create_shader_module(&include_wgsl!("shaders/text.wgsl"));
+        let cells_size = (std::mem::size_of::<Cell>() * MAX_COLUMNS * HUD_ROWS) as u64;
+
+        let bind_group_layout = rc.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("text_renderer::bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(std::mem::size_of::<Locals>() as _),
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(cells_size),
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = rc.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("text_renderer::pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = rc.device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("text_renderer::pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleStrip,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[ColorTargetState {
+                    format: rc.surface_format(),
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                }],
+            }),
+            multiview: None,
+        });
+
+        let locals = rc.device.create_buffer(&BufferDescriptor {
+            label: Some("text_renderer::locals"),
+            size: std::mem::size_of::<Locals>() as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+
+        let cells = rc.device.create_buffer(&BufferDescriptor {
+            label: Some("text_renderer::cells"),
+            size: cells_size,
+            usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let (atlas_data, atlas_size) = atlas_data()?;
+        let atlas = rc.device.create_texture(&TextureDescriptor {
+            label: Some("text_renderer::atlas"),
+            size: atlas_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: rc.surface_format(),
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+        });
+
+        rc.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &atlas,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &atlas_data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(atlas_size.width * rc.surface_format().describe().block_size as u32),
+                rows_per_image: None,
+            },
+            atlas_size,
+        );
+
+        let atlas_view = atlas.create_view(&TextureViewDescriptor {
+            label: Some("text_renderer::atlas_view"),
+            format: Some(rc.surface_format()),
+            dimension: Some(TextureViewDimension::D2),
+            aspect: TextureAspect::All,
+            ..Default::default()
+        });
+
+        let bind_group = rc.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("text_renderer::bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: locals.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Buffer(BufferBinding {
+                        buffer: &cells,
+                        offset: 0,
+                        size: BufferSize::new(cells_size),
+                    }),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(&atlas_view),
+                },
+            ],
+        });
+
+        Ok(Self {
+            pipeline,
+            locals,
+            cells,
+            bind_group,
+        })
+    }
+
+    /// Write the lines shown in the HUD along with their colors, top to bottom
+    ///
+    /// Lines past `HUD_ROWS` and characters past `MAX_COLUMNS` are cut off.
+    pub fn write_lines(&self, rc: &RenderingContext, lines: &[(String, u32)]) {
+        let mut cells = vec![Cell::default(); MAX_COLUMNS * HUD_ROWS];
+        for (row, (text, color)) in lines.iter().take(HUD_ROWS).enumerate() {
+            for (column, character) in text.chars().take(MAX_COLUMNS).enumerate() {
+                // Anything outside of ASCII has no glyph, so is drawn as a question mark
+                let character = if character.is_ascii() { character } else { '?' };
+                cells[row * MAX_COLUMNS + column] = Cell { character: character as u32, color: *color };
+            }
+        }
+
+        rc.queue.write_buffer(&self.cells, 0, bytemuck::cast_slice(&cells));
+    }
+
+    pub fn render(
+        &self,
+        rc: &RenderingContext,
+        surface_view: &TextureView,
+        resolution: Resolution,
+    ) {
+        // The panel spans the bottom of the screen, so give up if the window is too short for it
+        let height = GLYPH_HEIGHT * HUD_ROWS as u32;
+        if resolution.height < height || resolution.width == 0 {
+            return;
+        }
+
+        let locals = Locals {
+            origin: [0, resolution.height - height],
+            columns: (resolution.width / GLYPH_WIDTH).min(MAX_COLUMNS as u32),
+            rows: HUD_ROWS as u32,
+        };
+
+        rc.queue.write_buffer(&self.locals, 0, bytemuck::bytes_of(&locals));
+
+        // Build our command encoder
+        let mut command_encoder = rc.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command_encoder"),
+        });
+
+        // Render the panel on top of everything else
+        {
+            let mut render_pass = command_encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("text_renderer::render_pass"),
+                color_attachments: &[RenderPassColorAttachment {
+                    view: surface_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_scissor_rect(0, locals.origin[1], resolution.width, height);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        }
+
+        // Submit our work
+        rc.queue.submit([command_encoder.finish()]);
+    }
+}
+
+fn atlas_data() -> Result<(Vec<u8>, Extent3d), Error> {
+    let data = std::fs::read("./textures/font.gif")?;
+    let image = image::load_from_memory(&data)?.into_rgba8();
+    let (width, height) = image.dimensions();
+
+    Ok((image.into_raw(), Extent3d { width, height, depth_or_array_layers: 1 }))
+}
@@ -0,0 +1,23 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Health {
+    pub current: i32,
+    pub maximum: i32,
+}
+
+impl Health {
+    pub fn new(maximum: i32) -> Self {
+        Self { current: maximum, maximum }
+    }
+
+    /// Get the remaining health as a fraction of the maximum
+    pub fn fraction(&self) -> f32 {
+        self.current as f32 / self.maximum.max(1) as f32
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0
+    }
+}
@@ -0,0 +1,179 @@
+use crate::ecs::EntityId;
+use crate::item::ItemKind;
+use crate::log::Event;
+use crate::rng::Stream;
+use crate::world::World;
+
+/// What potions can look like, along with the color their sprite is drawn in
+const POTIONS: &[(&str, u32)] = &[
+    ("murky", 0xff30_5060),
+    ("bubbling", 0xff40_c040),
+    ("golden", 0xff20_c0e0),
+    ("violet", 0xffc0_40a0),
+    ("smoky", 0xff80_8080),
+    ("fizzy", 0xffe0_c060),
+];
+
+/// The labels scrolls can be found with
+const SCROLLS: &[&str] = &["ZELGO MER", "FOOBIE BLETCH", "ELBIB YLOH", "VERR YED HORRE", "JUYED AWK YACC"];
+
+/// A family of items that all look alike until the player learns which is which
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Category {
+    Potion,
+    Scroll,
+}
+
+impl Category {
+    pub const ALL: [Category; 2] = [Category::Potion, Category::Scroll];
+
+    /// Get every item kind in a specific category
+    pub fn kinds(self) -> &'static [ItemKind] {
+        match self {
+            Category::Potion => &[ItemKind::HealthPotion, ItemKind::SpeedPotion],
+            Category::Scroll => &[ItemKind::ScrollOfIdentify, ItemKind::ScrollOfTeleport],
+        }
+    }
+
+    /// Get the number of appearances the kinds of a specific category are given out from
+    fn appearances(self) -> usize {
+        match self {
+            Category::Potion => POTIONS.len(),
+            Category::Scroll => SCROLLS.len(),
+        }
+    }
+}
+
+impl ItemKind {
+    /// Get the category of a specific item kind, if it has to be identified before it's recognised
+    pub fn category(self) -> Option<Category> {
+        Category::ALL.into_iter().find(|category| category.kinds().contains(&self))
+    }
+}
+
+impl World {
+    /// Get the index of the appearance an item kind has been given in this world
+    ///
+    /// Appearances are shuffled from the seed alone, so they're the same every time a world is
+    /// loaded without having to be saved.
+    fn appearance(&self, kind: ItemKind) -> Option<usize> {
+        let category = kind.category()?;
+        let mut rng = self.derive_rng(Stream::Appearances, &[category as u64]);
+        let mut order: Vec<usize> = (0..category.appearances()).collect();
+        for i in (1..order.len()).rev() {
+            order.swap(i, rng.range(0..i as i32 + 1) as usize);
+        }
+
+        let index = category.kinds().iter().position(|other| *other == kind)?;
+        Some(order[index])
+    }
+
+    /// Whether the player knows what an item kind is, which is always true of those without a category
+    pub fn is_identified(&self, kind: ItemKind) -> bool {
+        kind.category().is_none() || self.identified.contains(&kind)
+    }
+
+    /// Get what an item kind looks like before it's identified, as in "murky potion"
+    pub fn appearance_name(&self, kind: ItemKind) -> Option<String> {
+        let index = self.appearance(kind)?;
+        match kind.category()? {
+            Category::Potion => Some(format!("{} potion", POTIONS[index].0)),
+            Category::Scroll => Some(format!("scroll labelled {}", SCROLLS[index])),
+        }
+    }
+
+    /// Get what the player calls an item kind, which is only its real name once it's identified
+    pub fn item_name(&self, kind: ItemKind) -> String {
+        match self.is_identified(kind) {
+            true => kind.name().to_string(),
+            false => self.appearance_name(kind).unwrap_or_else(|| kind.name().to_string()),
+        }
+    }
+
+    /// Get the color of an item kind's sprite, which for potions depends on their appearance
+    pub fn item_color(&self, kind: ItemKind) -> u32 {
+        match (kind.category(), self.appearance(kind)) {
+            (Some(Category::Potion), Some(index)) => POTIONS[index].1,
+            _ => kind.color(),
+        }
+    }
+
+    /// Teach the player what an item kind is, returning whether they didn't know already
+    pub fn identify(&mut self, kind: ItemKind) -> bool {
+        if self.is_identified(kind) {
+            return false;
+        }
+
+        self.identified.insert(kind);
+        self.notify(Event::Identified(kind));
+        true
+    }
+
+    /// Identify the first item kind an entity is carrying that the player doesn't know yet
+    pub fn identify_carried(&mut self, actor: EntityId) -> Option<ItemKind> {
+        let inventory = self.inventories.get(&actor)?;
+        let kind = inventory.items.iter().map(|item| item.kind).find(|kind| !self.is_identified(*kind))?;
+        self.identify(kind);
+        Some(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::health::Health;
+    use crate::inventory::Inventory;
+    use crate::item::Item;
+    use crate::item::ItemKind;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::Category;
+
+    fn names(world: &World) -> Vec<String> {
+        Category::ALL.iter().flat_map(|category| category.kinds()).map(|kind| world.item_name(*kind)).collect()
+    }
+
+    #[test]
+    fn appearances_are_seeded() {
+        let world = |seed| World { seed, ..World::test([[Tile::Planks as u8; 16]; 16], [0, 0]) };
+        let first = world(1);
+        let unique: BTreeSet<String> = names(&first).into_iter().collect();
+        assert_eq!(unique.len(), names(&first).len());
+        assert!(!unique.contains(ItemKind::SpeedPotion.name()));
+        assert_eq!(first.item_name(ItemKind::Dagger), "dagger");
+
+        assert_eq!(names(&world(1)), names(&first));
+        assert!((2..20).any(|seed| names(&world(seed)) != names(&first)));
+    }
+
+    #[test]
+    fn using_items_identifies_them() {
+        let mut world = World { seed: 3, ..World::test([[Tile::Planks as u8; 16]; 16], [0, 0]) };
+        let player = world.player;
+        world.healths.insert(player, Health::new(10));
+        world.inventories.insert(player, Inventory::new(10, 100));
+        let inventory = world.inventories.get_mut(&player).unwrap();
+        for kind in [ItemKind::SpeedPotion, ItemKind::ScrollOfIdentify, ItemKind::HealthPotion] {
+            inventory.add(Item::new(kind, 1)).unwrap();
+        }
+
+        let appearance = world.item_name(ItemKind::SpeedPotion);
+        assert!(world.use_item(player, 0));
+        assert!(world.is_identified(ItemKind::SpeedPotion));
+        assert_eq!(world.item_name(ItemKind::SpeedPotion), "potion of speed");
+        assert!(world.log.lines(1)[0].text.contains(&format!("{appearance} is a potion of speed")));
+
+        // Reading the scroll identifies both itself and the potion carried alongside it
+        assert!(world.use_item(player, 0));
+        assert!(world.is_identified(ItemKind::ScrollOfIdentify));
+        assert!(world.is_identified(ItemKind::HealthPotion));
+        assert!(!world.is_identified(ItemKind::ScrollOfTeleport));
+
+        let saved = serde_json::to_string(&world).unwrap();
+        let loaded: World = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.identified, world.identified);
+        assert_eq!(names(&loaded), names(&world));
+    }
+}
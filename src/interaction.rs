@@ -0,0 +1,154 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ecs::EntityId;
+use crate::log::Event;
+use crate::loot::LootTable;
+use crate::tile::Tile;
+use crate::world::World;
+
+/// A lever and the tiles it toggles whenever it's pulled
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Lever {
+    pub position: [i32; 2],
+    pub targets: Vec<[i32; 2]>,
+}
+
+impl World {
+    /// Have an entity interact with the tile at an adjacent position, returning whether anything happened
+    pub fn interact(&mut self, actor: EntityId, position: [i32; 2]) -> bool {
+        let from = match self.positions.get(&actor) {
+            Some(from) => *from,
+            None => return false,
+        };
+
+        if (from[0] - position[0]).abs() > 1 || (from[1] - position[1]).abs() > 1 {
+            return false;
+        }
+
+        let tile = match self.tile(position) {
+            Some(tile) => tile,
+            None => return false,
+        };
+
+        let changed = match tile {
+            Tile::LeverOff | Tile::LeverOn => {
+                self.toggle(position);
+                let targets: Vec<[i32; 2]> = self.levers
+                    .iter()
+                    .filter(|lever| lever.position == position)
+                    .flat_map(|lever| lever.targets.iter().copied())
+                    .collect();
+
+                for target in targets {
+                    self.toggle(target);
+                }
+
+                true
+            }
+            Tile::Chest => {
+                self.toggle(position);
+                self.drop_loot(LootTable::Chest, position, &[position[0] as u64, position[1] as u64]);
+                true
+            }
+            _ => self.toggle(position),
+        };
+
+        if changed {
+            self.notify(Event::Interacted(actor, tile));
+        }
+
+        changed
+    }
+
+    /// Switch a tile to its other state, returning whether it could be switched
+    ///
+    /// Doors can't be closed while anything is in the way.
+    fn toggle(&mut self, position: [i32; 2]) -> bool {
+        let tile = match self.tile(position).and_then(|tile| tile.toggled()) {
+            Some(tile) => tile,
+            None => return false,
+        };
+
+        if tile.is_solid() && self.positions.values().any(|p| *p == position) {
+            return false;
+        }
+
+        self.set_tile(position, tile)
+    }
+
+    /// Find an adjacent tile matching a predicate
+    pub fn adjacent_tile<P>(&self, position: [i32; 2], predicate: P) -> Option<[i32; 2]>
+    where
+        P: Fn(Tile) -> bool,
+    {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| [position[0] + dx, position[1] + dy]))
+            .filter(|p| *p != position)
+            .find(|p| self.tile(*p).is_some_and(&predicate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::Lever;
+
+    fn world() -> World {
+        let mut chunk = [[Tile::Planks as u8; 16]; 16];
+        chunk[0][3] = Tile::DoorClosed as u8;
+        chunk[1][3] = Tile::LeverOff as u8;
+        chunk[2][3] = Tile::Chest as u8;
+        chunk[5][5] = Tile::DoorClosed as u8;
+
+        let mut world = World::test(chunk, [2, 1]);
+        world.levers.push(Lever { position: [3, 1], targets: vec![[5, 5]] });
+        world
+    }
+
+    #[test]
+    fn doors_toggle_and_mark_chunks_dirty() {
+        let mut world = world();
+        let player = world.player;
+        assert!(!world.is_passable([3, 0]));
+        assert!(world.interact(player, [3, 0]));
+        assert_eq!(world.tile([3, 0]), Some(Tile::DoorOpen));
+        assert!(world.is_passable([3, 0]));
+        assert!(world.dirty_chunks.contains(&0));
+
+        world.positions.insert(player, [3, 0]);
+        assert!(!world.interact(player, [3, 0]));
+        world.positions.insert(player, [2, 1]);
+        assert!(world.interact(player, [3, 0]));
+        assert_eq!(world.tile([3, 0]), Some(Tile::DoorClosed));
+        assert!(!world.interact(player, [9, 9]));
+    }
+
+    #[test]
+    fn levers_and_chests() {
+        let mut world = world();
+        let player = world.player;
+        assert!(world.interact(player, [3, 1]));
+        assert_eq!(world.tile([3, 1]), Some(Tile::LeverOn));
+        assert_eq!(world.tile([5, 5]), Some(Tile::DoorOpen));
+
+        assert!(world.interact(player, [3, 2]));
+        assert_eq!(world.tile([3, 2]), Some(Tile::ChestOpen));
+        assert!(!world.items_at([3, 2]).is_empty());
+        assert!(!world.interact(player, [3, 2]));
+    }
+
+    #[test]
+    fn changes_invalidate_visibility() {
+        let mut world = world();
+        let player = world.player;
+        assert!(world.update_visibility());
+        assert!(!world.update_visibility());
+
+        world.interact(player, [3, 1]);
+        assert!(world.update_visibility());
+        assert!(!world.update_visibility());
+    }
+}
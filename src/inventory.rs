@@ -0,0 +1,293 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ecs::EntityId;
+use crate::entity::Entity;
+use crate::item::Item;
+use crate::item::ItemKind;
+use crate::log::Event;
+use crate::status::StatusKind;
+use crate::world::World;
+
+/// The health restored by drinking a potion of health
+const POTION_HEALING: i32 = 10;
+const BOMB_RADIUS: i32 = 2;
+const BOMB_DAMAGE: u32 = 30;
+const SCROLL_TELEPORT_RADIUS: i32 = 20;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Inventory {
+    pub items: Vec<Item>,
+    pub max_slots: usize,
+    pub max_weight: u32,
+}
+
+impl Inventory {
+    pub fn new(max_slots: usize, max_weight: u32) -> Self {
+        Self { items: vec![], max_slots, max_weight }
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.items.iter().map(|item| item.weight()).sum()
+    }
+
+    /// Add a stack of items, topping up existing stacks before using new slots
+    ///
+    /// Whatever doesn't fit is handed back.
+    pub fn add(&mut self, mut item: Item) -> Result<(), Item> {
+        let capacity = match item.kind.weight() {
+            0 => u32::MAX,
+            weight => self.max_weight.saturating_sub(self.weight()) / weight,
+        };
+        let overflow = item.count.saturating_sub(capacity);
+        item.count -= overflow;
+
+        for stack in self.items.iter_mut() {
+            if !stack.stacks_with(&item) {
+                continue;
+            }
+
+            let moved = item.count.min(item.kind.max_stack().saturating_sub(stack.count));
+            stack.count += moved;
+            item.count -= moved;
+        }
+
+        while item.count > 0 && self.items.len() < self.max_slots {
+            let moved = item.count.min(item.kind.max_stack());
+            self.items.push(Item { count: moved, ..item.clone() });
+            item.count -= moved;
+        }
+
+        item.count += overflow;
+        if item.count > 0 {
+            Err(item)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Remove up to `count` items from a slot, freeing the slot if it empties
+    pub fn take(&mut self, slot: usize, count: u32) -> Option<Item> {
+        let stack = self.items.get_mut(slot)?;
+        let taken = Item { count: count.min(stack.count), ..stack.clone() };
+        stack.count -= taken.count;
+        if stack.count == 0 {
+            self.items.remove(slot);
+        }
+
+        Some(taken)
+    }
+
+    /// Count the items of a kind across every slot
+    pub fn count(&self, kind: ItemKind) -> u32 {
+        self.items.iter().filter(|item| item.kind == kind).map(|item| item.count).sum()
+    }
+
+    /// Remove `count` items of a kind from whichever slots hold them, or nothing if there aren't enough
+    pub fn remove(&mut self, kind: ItemKind, count: u32) -> bool {
+        if self.count(kind) < count {
+            return false;
+        }
+
+        let mut remaining = count;
+        while remaining > 0 {
+            let slot = match self.items.iter().rposition(|item| item.kind == kind) {
+                Some(slot) => slot,
+                None => break,
+            };
+
+            remaining -= self.take(slot, remaining).map(|item| item.count).unwrap_or_default();
+        }
+
+        true
+    }
+}
+
+impl World {
+    /// Place a stack of items on the ground
+    pub fn spawn_item(&mut self, item: Item, position: [i32; 2]) -> EntityId {
+        let id = self.spawn();
+        let sprite_position = [position[0] as f32, position[1] as f32];
+        let color = self.item_color(item.kind);
+        self.entities.insert(id, Entity::new(sprite_position, item.kind.atlas_position(), [1, 1], color, None));
+        self.positions.insert(id, position);
+        self.items.insert(id, item);
+        id
+    }
+
+    /// Put an item in the player's inventory, leaving whatever they can't carry at their feet
+    pub fn give_player(&mut self, item: Item) {
+        let overflow = match self.inventories.get_mut(&self.player) {
+            Some(inventory) => inventory.add(item).err(),
+            None => Some(item),
+        };
+
+        if let (Some(item), Some(position)) = (overflow, self.positions.get(&self.player).copied()) {
+            self.spawn_item(item, position);
+        }
+    }
+
+    /// Get every item lying at a position
+    pub fn items_at(&self, position: [i32; 2]) -> Vec<EntityId> {
+        self.items
+            .keys()
+            .filter(|id| self.positions.get(id) == Some(&position))
+            .copied()
+            .collect()
+    }
+
+    /// Pick up the items at an entity's feet, returning whether anything was picked up
+    pub fn pick_up(&mut self, actor: EntityId) -> bool {
+        let position = match self.positions.get(&actor) {
+            Some(position) => *position,
+            None => return false,
+        };
+
+        let mut picked_up = false;
+        for id in self.items_at(position) {
+            let inventory = match self.inventories.get_mut(&actor) {
+                Some(inventory) => inventory,
+                None => return false,
+            };
+
+            let item = self.items[&id].clone();
+            let taken = match inventory.add(item.clone()) {
+                Ok(()) => {
+                    self.despawn(id);
+                    item.count
+                }
+                Err(remainder) => {
+                    let taken = item.count - remainder.count;
+                    self.items.insert(id, remainder);
+                    taken
+                }
+            };
+
+            if taken > 0 {
+                self.notify(Event::PickedUp(actor, Item { count: taken, ..item }));
+                picked_up = true;
+            }
+        }
+
+        picked_up
+    }
+
+    /// Drop a whole inventory slot at an entity's feet
+    pub fn drop_item(&mut self, actor: EntityId, slot: usize) -> bool {
+        let position = match self.positions.get(&actor) {
+            Some(position) => *position,
+            None => return false,
+        };
+
+        let item = match self.inventories.get_mut(&actor).and_then(|inventory| inventory.take(slot, u32::MAX)) {
+            Some(item) => item,
+            None => return false,
+        };
+
+        self.spawn_item(item, position);
+        true
+    }
+
+    /// Use a single item from an inventory slot, returning whether it had any effect
+    pub fn use_item(&mut self, actor: EntityId, slot: usize) -> bool {
+        let kind = match self.inventories.get(&actor).and_then(|inventory| inventory.items.get(slot)) {
+            Some(item) => item.kind,
+            None => return false,
+        };
+
+        let used = match kind {
+            ItemKind::HealthPotion => match self.healths.get_mut(&actor) {
+                Some(health) => {
+                    health.current = (health.current + POTION_HEALING).min(health.maximum);
+                    true
+                }
+                None => false,
+            },
+            ItemKind::Bomb => self.place_bomb(actor, BOMB_RADIUS, BOMB_DAMAGE),
+            ItemKind::SpeedPotion => match self.healths.contains_key(&actor) {
+                true => {
+                    self.apply_status(actor, StatusKind::Haste);
+                    true
+                }
+                false => false,
+            },
+            ItemKind::ScrollOfIdentify => true,
+            ItemKind::ScrollOfTeleport => self.teleport(actor, SCROLL_TELEPORT_RADIUS),
+            _ => false,
+        };
+
+        if used {
+            self.inventories.get_mut(&actor).unwrap().take(slot, 1);
+            self.notify(Event::Used(actor, kind));
+            if actor == self.player {
+                self.identify(kind);
+                if kind == ItemKind::ScrollOfIdentify {
+                    self.identify_carried(actor);
+                }
+            }
+        }
+
+        used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::health::Health;
+    use crate::item::Item;
+    use crate::item::ItemKind;
+    use crate::world::World;
+
+    use super::Inventory;
+
+    #[test]
+    fn stacking() {
+        let mut inventory = Inventory::new(3, 1000);
+        inventory.add(Item::new(ItemKind::Arrow, 60)).unwrap();
+        inventory.add(Item::new(ItemKind::Arrow, 60)).unwrap();
+        assert_eq!(inventory.items, vec![Item::new(ItemKind::Arrow, 99), Item::new(ItemKind::Arrow, 21)]);
+
+        inventory.add(Item::new(ItemKind::Dagger, 1)).unwrap();
+        assert_eq!(inventory.add(Item::new(ItemKind::Dagger, 1)), Err(Item::new(ItemKind::Dagger, 1)));
+        inventory.add(Item::new(ItemKind::Arrow, 10)).unwrap();
+        assert_eq!(inventory.items[1].count, 31);
+    }
+
+    #[test]
+    fn weight_limit() {
+        let mut inventory = Inventory::new(10, 12);
+        assert_eq!(inventory.add(Item::new(ItemKind::Rock, 3)), Err(Item::new(ItemKind::Rock, 1)));
+        assert_eq!(inventory.weight(), 10);
+        assert_eq!(inventory.take(0, 5), Some(Item::new(ItemKind::Rock, 2)));
+        assert!(inventory.items.is_empty());
+    }
+
+    #[test]
+    fn pick_up_drop_and_use() {
+        let mut world = World::default();
+        let player = world.player;
+        world.positions.insert(player, [1, 1]);
+        world.healths.insert(player, Health { current: 5, maximum: 20 });
+        world.inventories.insert(player, Inventory::new(4, 100));
+
+        world.spawn_item(Item::new(ItemKind::HealthPotion, 2), [1, 1]);
+        world.spawn_item(Item::new(ItemKind::Dagger, 1), [1, 1]);
+        assert_eq!(world.entity_at([1, 1]), Some(player));
+
+        assert!(world.pick_up(player));
+        assert!(world.items_at([1, 1]).is_empty());
+        assert_eq!(world.inventories[&player].items.len(), 2);
+
+        assert!(world.use_item(player, 0));
+        assert_eq!(world.healths[&player].current, 15);
+        assert_eq!(world.inventories[&player].items[0].count, 1);
+        assert!(!world.use_item(player, 1));
+
+        assert!(world.drop_item(player, 1));
+        assert_eq!(world.items_at([1, 1]).len(), 1);
+
+        let saved = serde_json::to_string(&world).unwrap();
+        let loaded: World = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.inventories[&player], world.inventories[&player]);
+    }
+}
@@ -0,0 +1,229 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::equipment::EquipmentSlot;
+use crate::equipment::Modifiers;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum ItemKind {
+    Arrow,
+    Rock,
+    Dagger,
+    LeatherArmor,
+    RingOfSwiftness,
+    Torch,
+    HealthPotion,
+    Bomb,
+    Gold,
+    SpeedPotion,
+    ScrollOfIdentify,
+    ScrollOfTeleport,
+}
+
+impl ItemKind {
+    /// Get the display name of a specific item kind
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemKind::Arrow => "arrow",
+            ItemKind::Rock => "rock",
+            ItemKind::Dagger => "dagger",
+            ItemKind::LeatherArmor => "leather armor",
+            ItemKind::RingOfSwiftness => "ring of swiftness",
+            ItemKind::Torch => "torch",
+            ItemKind::HealthPotion => "potion of health",
+            ItemKind::Bomb => "bomb",
+            ItemKind::Gold => "gold",
+            ItemKind::SpeedPotion => "potion of speed",
+            ItemKind::ScrollOfIdentify => "scroll of identify",
+            ItemKind::ScrollOfTeleport => "scroll of teleportation",
+        }
+    }
+
+    /// Get the weight of a single item of a specific kind
+    pub fn weight(self) -> u32 {
+        match self {
+            ItemKind::Arrow => 1,
+            ItemKind::Rock => 5,
+            ItemKind::Dagger => 10,
+            ItemKind::LeatherArmor => 30,
+            ItemKind::RingOfSwiftness => 1,
+            ItemKind::Torch => 5,
+            ItemKind::HealthPotion => 3,
+            ItemKind::Bomb => 4,
+            ItemKind::Gold => 0,
+            ItemKind::SpeedPotion => 3,
+            ItemKind::ScrollOfIdentify => 1,
+            ItemKind::ScrollOfTeleport => 1,
+        }
+    }
+
+    /// Get the number of items of a specific kind that fit in a single inventory slot
+    pub fn max_stack(self) -> u32 {
+        match self {
+            ItemKind::Arrow => 99,
+            ItemKind::Rock => 10,
+            ItemKind::Dagger => 1,
+            ItemKind::LeatherArmor => 1,
+            ItemKind::RingOfSwiftness => 1,
+            ItemKind::Torch => 5,
+            ItemKind::HealthPotion => 10,
+            ItemKind::Bomb => 5,
+            ItemKind::Gold => 9999,
+            ItemKind::SpeedPotion => 10,
+            ItemKind::ScrollOfIdentify => 10,
+            ItemKind::ScrollOfTeleport => 10,
+        }
+    }
+
+    /// Get the position of a specific item kind's sprite in the entity atlas
+    pub fn atlas_position(self) -> [u32; 2] {
+        match self {
+            ItemKind::Arrow => [0, 1],
+            ItemKind::Rock => [1, 1],
+            ItemKind::Dagger => [2, 1],
+            ItemKind::LeatherArmor => [4, 1],
+            ItemKind::RingOfSwiftness => [5, 1],
+            ItemKind::Torch => [6, 1],
+            ItemKind::HealthPotion => [3, 1],
+            ItemKind::Bomb => [7, 1],
+            ItemKind::Gold => [0, 3],
+            ItemKind::SpeedPotion => [3, 1],
+            ItemKind::ScrollOfIdentify => [3, 3],
+            ItemKind::ScrollOfTeleport => [3, 3],
+        }
+    }
+
+    /// Get the color of a specific item kind's sprite
+    pub fn color(self) -> u32 {
+        match self {
+            ItemKind::Arrow => 0xff40_80c0,
+            ItemKind::Rock => 0xff80_8080,
+            ItemKind::Dagger => 0xffc0_c0c0,
+            ItemKind::LeatherArmor => 0xff20_4080,
+            ItemKind::RingOfSwiftness => 0xff00_c0ff,
+            ItemKind::Torch => 0xff00_80ff,
+            ItemKind::HealthPotion => 0xff20_20e0,
+            ItemKind::Bomb => 0xff30_3030,
+            ItemKind::Gold => 0xff00_d0ff,
+            ItemKind::SpeedPotion => 0xff20_e0e0,
+            ItemKind::ScrollOfIdentify => 0xffd0_f0f0,
+            ItemKind::ScrollOfTeleport => 0xffd0_f0f0,
+        }
+    }
+
+    /// Get what a single item of a specific kind is worth in gold, before any shop's cut
+    pub fn value(self) -> u32 {
+        match self {
+            ItemKind::Arrow => 1,
+            ItemKind::Rock => 0,
+            ItemKind::Dagger => 30,
+            ItemKind::LeatherArmor => 40,
+            ItemKind::RingOfSwiftness => 120,
+            ItemKind::Torch => 8,
+            ItemKind::HealthPotion => 25,
+            ItemKind::Bomb => 35,
+            ItemKind::Gold => 1,
+            ItemKind::SpeedPotion => 40,
+            ItemKind::ScrollOfIdentify => 20,
+            ItemKind::ScrollOfTeleport => 30,
+        }
+    }
+}
+
+impl ItemKind {
+    /// Get the equipment slot a specific item kind is worn in, if it can be worn at all
+    pub fn slot(self) -> Option<EquipmentSlot> {
+        match self {
+            ItemKind::Dagger => Some(EquipmentSlot::Weapon),
+            ItemKind::LeatherArmor => Some(EquipmentSlot::Armor),
+            ItemKind::RingOfSwiftness => Some(EquipmentSlot::Ring),
+            ItemKind::Torch => Some(EquipmentSlot::LightSource),
+            _ => None,
+        }
+    }
+
+    /// Get the stat modifiers granted by wearing a specific item kind
+    pub fn modifiers(self) -> Modifiers {
+        match self {
+            ItemKind::Dagger => Modifiers { damage: 2, accuracy: 5, ..Default::default() },
+            ItemKind::LeatherArmor => Modifiers { armor: 2, speed: -1, ..Default::default() },
+            ItemKind::RingOfSwiftness => Modifiers { evasion: 5, speed: 2, ..Default::default() },
+            _ => Modifiers::default(),
+        }
+    }
+
+    /// Get the color and magnitude of the light given off by a specific item kind when held
+    pub fn light(self) -> Option<([u8; 3], u8)> {
+        match self {
+            ItemKind::Torch => Some(([255, 160, 64], 200)),
+            _ => None,
+        }
+    }
+}
+
+/// A random property rolled onto an item, on top of those of its kind
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Affix {
+    Sharp,
+    Accurate,
+    Sturdy,
+    Nimble,
+    Swift,
+}
+
+impl Affix {
+    pub const ALL: [Affix; 5] = [Affix::Sharp, Affix::Accurate, Affix::Sturdy, Affix::Nimble, Affix::Swift];
+
+    /// Whether a specific affix can roll on items worn in a slot
+    pub fn applies_to(self, slot: EquipmentSlot) -> bool {
+        match self {
+            Affix::Sharp | Affix::Accurate => slot == EquipmentSlot::Weapon,
+            Affix::Sturdy => slot == EquipmentSlot::Armor,
+            Affix::Nimble | Affix::Swift => slot != EquipmentSlot::LightSource,
+        }
+    }
+
+    pub fn modifiers(self) -> Modifiers {
+        match self {
+            Affix::Sharp => Modifiers { damage: 1, ..Default::default() },
+            Affix::Accurate => Modifiers { accuracy: 10, ..Default::default() },
+            Affix::Sturdy => Modifiers { armor: 1, ..Default::default() },
+            Affix::Nimble => Modifiers { evasion: 5, ..Default::default() },
+            Affix::Swift => Modifiers { speed: 1, ..Default::default() },
+        }
+    }
+}
+
+/// A stack of one or more items of the same kind
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub count: u32,
+    #[serde(default)]
+    pub affixes: Vec<Affix>,
+}
+
+impl Item {
+    pub fn new(kind: ItemKind, count: u32) -> Self {
+        Self { kind, count, affixes: vec![] }
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.kind.weight() * self.count
+    }
+
+    /// Whether another stack can be merged into this one
+    pub fn stacks_with(&self, other: &Item) -> bool {
+        self.kind == other.kind && self.affixes == other.affixes && self.kind.max_stack() > 1
+    }
+
+    /// Get the stat modifiers granted by wearing this item, including its affixes
+    pub fn modifiers(&self) -> Modifiers {
+        self.affixes.iter().fold(self.kind.modifiers(), |total, affix| total + affix.modifiers())
+    }
+
+    /// Get what a single one of these items is worth, with each affix adding half again
+    pub fn value(&self) -> u32 {
+        self.kind.value() * (2 + self.affixes.len() as u32) / 2
+    }
+}
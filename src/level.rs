@@ -0,0 +1,52 @@
+/// A rectangle of tiles
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Rect {
+    pub position: [i32; 2],
+    pub size: [i32; 2],
+}
+
+impl Rect {
+    pub fn new(position: [i32; 2], size: [i32; 2]) -> Self {
+        Self { position, size }
+    }
+
+    pub fn contains(&self, position: [i32; 2]) -> bool {
+        (0..2).all(|i| position[i] >= self.position[i] && position[i] < self.position[i] + self.size[i])
+    }
+
+    /// Iterate over every tile inside the rectangle, row by row
+    pub fn tiles(&self) -> impl Iterator<Item = [i32; 2]> {
+        let [x, y] = self.position;
+        let [width, height] = self.size;
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| [x, y]))
+    }
+}
+
+/// The shape of a level as laid out by map generation, which later stages fill in
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Layout {
+    pub rooms: Vec<Rect>,
+    /// Areas claimed by prefabs, which nothing else may be placed in
+    pub reserved: Vec<Rect>,
+    /// Where the player enters the level
+    pub start: [i32; 2],
+}
+
+impl Layout {
+    pub fn is_reserved(&self, position: [i32; 2]) -> bool {
+        self.reserved.iter().any(|rect| rect.contains(position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rect;
+
+    #[test]
+    fn rect_tiles() {
+        let rect = Rect::new([2, 3], [2, 2]);
+        assert_eq!(rect.tiles().collect::<Vec<_>>(), vec![[2, 3], [3, 3], [2, 4], [3, 4]]);
+        assert!(rect.contains([3, 4]));
+        assert!(!rect.contains([4, 4]));
+    }
+}
@@ -2,6 +2,9 @@ use bytemuck::Zeroable;
 use serde::Deserialize;
 use serde::Serialize;
 
+/// The distance in tiles at which a light stops lighting anything
+pub const LIGHT_RADIUS: i32 = 8;
+
 #[repr(C, align(256))]
 #[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, Zeroable)]
 pub struct Light {
@@ -14,6 +17,23 @@ impl Light {
     pub fn new(position: [f32; 2], color: [u8; 3], magnitude: u8) -> Self {
         Self { position, color, magnitude }
     }
+
+    pub fn position(&self) -> [f32; 2] {
+        self.position
+    }
+
+    /// Get how brightly the light shines on something a number of tiles away, from 0 to 1
+    ///
+    /// Brightness falls off roughly with the square of the distance, reaching nothing at `LIGHT_RADIUS`.
+    pub fn brightness(&self, distance: f32) -> f32 {
+        let [radius_squared, distance_squared] = [(LIGHT_RADIUS * LIGHT_RADIUS) as f32, distance * distance];
+        let falloff = (radius_squared - distance_squared) / (radius_squared * (distance_squared + 1.0));
+        self.magnitude as f32 / 255.0 * falloff.max(0.0)
+    }
+
+    pub fn set_position(&mut self, position: [f32; 2]) {
+        self.position = position;
+    }
 }
 
 #[cfg(test)]
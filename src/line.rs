@@ -0,0 +1,39 @@
+/// Get the tiles along a line between two points with Bresenham's algorithm, excluding `from`
+pub fn bresenham(from: [i32; 2], to: [i32; 2]) -> Vec<[i32; 2]> {
+    let dx = (to[0] - from[0]).abs();
+    let dy = -(to[1] - from[1]).abs();
+    let sx = (to[0] - from[0]).signum();
+    let sy = (to[1] - from[1]).signum();
+
+    let mut tiles = vec![];
+    let [mut x, mut y] = from;
+    let mut error = dx + dy;
+    while [x, y] != to {
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            error += dx;
+            y += sy;
+        }
+
+        tiles.push([x, y]);
+    }
+
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bresenham;
+
+    #[test]
+    fn lines() {
+        assert_eq!(bresenham([0, 0], [0, 0]), Vec::<[i32; 2]>::new());
+        assert_eq!(bresenham([0, 0], [3, 0]), vec![[1, 0], [2, 0], [3, 0]]);
+        assert_eq!(bresenham([0, 0], [-2, -2]), vec![[-1, -1], [-2, -2]]);
+        assert_eq!(bresenham([0, 0], [4, 2]), vec![[1, 1], [2, 1], [3, 2], [4, 2]]);
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ability::AbilityKind;
+use crate::combat::AttackOutcome;
+use crate::ecs::EntityId;
+use crate::faction::Faction;
+use crate::item::Item;
+use crate::item::ItemKind;
+use crate::status::StatusKind;
+use crate::tile::Tile;
+use crate::trap::TrapKind;
+use crate::world::World;
+
+/// The number of messages kept for scrolling back through
+pub const MAX_MESSAGES: usize = 200;
+
+pub const WHITE: u32 = 0xffff_ffff;
+pub const GREY: u32 = 0xffa0_a0a0;
+pub const RED: u32 = 0xff40_40ff;
+pub const GREEN: u32 = 0xff40_ff40;
+pub const YELLOW: u32 = 0xff40_ffff;
+pub const PURPLE: u32 = 0xffff_60c0;
+
+/// Something that happened which the player should hear about
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Attacked { attacker: EntityId, defender: EntityId, outcome: AttackOutcome },
+    Killed(EntityId),
+    PickedUp(EntityId, Item),
+    Used(EntityId, ItemKind),
+    Equipped(EntityId, ItemKind),
+    StatusApplied(EntityId, StatusKind),
+    Interacted(EntityId, Tile),
+    LevelledUp(u32),
+    TrapTriggered(EntityId, TrapKind),
+    TrapFound(EntityId, TrapKind),
+    SecretDoorFound(EntityId),
+    /// The player was given items by someone else
+    Received(Item),
+    /// The player handed items over to someone else
+    HandedOver(Item),
+    /// A faction's attitude to the player changed enough to start or stop fighting them
+    ReputationChanged { faction: Faction, hostile: bool },
+    /// The player bought an item from a shop for a price in gold
+    Bought(ItemKind, u32),
+    Sold(ItemKind, u32),
+    AbilityUsed(EntityId, AbilityKind),
+    /// The player learned what an item kind they'd only seen the appearance of really is
+    Identified(ItemKind),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Message {
+    pub text: String,
+    pub color: u32,
+    /// How many times in a row the message was received
+    pub count: u32,
+}
+
+impl Message {
+    pub fn display(&self) -> String {
+        match self.count {
+            1 => self.text.clone(),
+            count => format!("{} x{}", self.text, count),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MessageLog {
+    messages: VecDeque<Message>,
+    /// How many messages back from the newest the view is scrolled
+    #[serde(skip)]
+    scroll: usize,
+}
+
+impl MessageLog {
+    /// Add a message, merging it into the newest if it's a repeat
+    pub fn push(&mut self, text: String, color: u32) {
+        if let Some(newest) = self.messages.back_mut() {
+            if newest.text == text && newest.color == color {
+                newest.count += 1;
+                return;
+            }
+        }
+
+        self.messages.push_back(Message { text, color, count: 1 });
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.pop_front();
+        }
+
+        // Keep the view on the same messages if the player has scrolled back
+        if self.scroll > 0 {
+            self.scroll = (self.scroll + 1).min(self.messages.len().saturating_sub(1));
+        }
+    }
+
+    /// Scroll back through older messages, or forwards if `lines` is negative
+    pub fn scroll(&mut self, lines: i32) {
+        let scroll = self.scroll as i32 + lines;
+        self.scroll = scroll.clamp(0, self.messages.len().saturating_sub(1) as i32) as usize;
+    }
+
+    /// Get up to `count` messages ending at the scroll position, oldest first
+    pub fn lines(&self, count: usize) -> Vec<&Message> {
+        let end = self.messages.len() - self.scroll.min(self.messages.len());
+        let start = end.saturating_sub(count);
+        self.messages.range(start..end).collect()
+    }
+}
+
+/// Uppercase the first letter of a sentence
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl World {
+    /// Get the name an entity goes by in messages
+    pub fn name_of(&self, id: EntityId) -> String {
+        if id == self.player {
+            return "you".to_string();
+        }
+
+        match self.names.get(&id) {
+            Some(name) => format!("the {name}"),
+            None => "something".to_string(),
+        }
+    }
+
+    /// Whether the player knows about something an entity did
+    fn is_noticed(&self, id: EntityId) -> bool {
+        id == self.player || self.positions.get(&id).is_some_and(|p| self.visibility.is_visible(*p))
+    }
+
+    /// Get the lines shown in the HUD, the player's status followed by the newest messages
+    pub fn hud_lines(&self, rows: usize) -> Vec<(String, u32)> {
+        let mut status = match self.healths.get(&self.player) {
+            Some(health) if !self.is_dead() => format!("HP {}/{}", health.current, health.maximum),
+            _ => "Dead".to_string(),
+        };
+
+        if let Some(summary) = self.progression_summary() {
+            status.push_str("  ");
+            status.push_str(&summary);
+        }
+
+        if let Some(summary) = self.ability_summary() {
+            status.push_str("  ");
+            status.push_str(&summary);
+        }
+
+        let mut lines = vec![(status, WHITE)];
+        for message in self.log.lines(rows.saturating_sub(1)) {
+            lines.push((message.display(), message.color));
+        }
+
+        lines
+    }
+
+    /// Describe an event in the message log if the player would notice it
+    pub fn notify(&mut self, event: Event) {
+        let (text, color) = match event {
+            Event::Attacked { attacker, defender, outcome } => {
+                if !self.is_noticed(attacker) && !self.is_noticed(defender) {
+                    return;
+                }
+
+                let (attacker_name, defender_name) = (self.name_of(attacker), self.name_of(defender));
+                let player = attacker == self.player;
+                let color = if defender == self.player { RED } else { WHITE };
+                let text = match (outcome, player) {
+                    (AttackOutcome::Miss, true) => format!("you miss {defender_name}"),
+                    (AttackOutcome::Miss, false) => format!("{attacker_name} misses {defender_name}"),
+                    (AttackOutcome::Hit(_), true) => format!("you hit {defender_name}"),
+                    (AttackOutcome::Hit(_), false) => format!("{attacker_name} hits {defender_name}"),
+                    (AttackOutcome::Critical(_), true) => format!("you critically hit {defender_name}"),
+                    (AttackOutcome::Critical(_), false) => format!("{attacker_name} critically hits {defender_name}"),
+                };
+
+                (text, if outcome == AttackOutcome::Miss { GREY } else { color })
+            }
+            Event::Killed(id) => {
+                if !self.is_noticed(id) {
+                    return;
+                }
+
+                match id == self.player {
+                    true => ("you die...".to_string(), RED),
+                    false => (format!("{} dies", self.name_of(id)), YELLOW),
+                }
+            }
+            Event::PickedUp(id, item) if id == self.player => {
+                let text = match item.count {
+                    1 => format!("you pick up the {}", self.item_name(item.kind)),
+                    count => format!("you pick up {} x{}", self.item_name(item.kind), count),
+                };
+
+                (text, WHITE)
+            }
+            Event::Used(id, kind) if id == self.player => match kind {
+                ItemKind::HealthPotion | ItemKind::SpeedPotion => {
+                    (format!("you drink the {}", self.item_name(kind)), GREEN)
+                }
+                ItemKind::ScrollOfIdentify | ItemKind::ScrollOfTeleport => {
+                    (format!("you read the {}", self.item_name(kind)), WHITE)
+                }
+                ItemKind::Bomb => (format!("you light the {}", kind.name()), YELLOW),
+                _ => (format!("you use the {}", self.item_name(kind)), WHITE),
+            },
+            Event::Equipped(id, kind) if id == self.player => (format!("you equip the {}", kind.name()), WHITE),
+            Event::StatusApplied(id, kind) => {
+                if !self.is_noticed(id) {
+                    return;
+                }
+
+                let verb = if id == self.player { "are" } else { "is" };
+                let color = if id == self.player { PURPLE } else { WHITE };
+                (format!("{} {} {}", self.name_of(id), verb, kind.definition().adjective), color)
+            }
+            Event::Interacted(id, tile) if id == self.player => {
+                let text = match tile {
+                    Tile::DoorClosed => "you open the door",
+                    Tile::DoorOpen => "you close the door",
+                    Tile::LeverOff | Tile::LeverOn => "you pull the lever",
+                    Tile::Chest => "you open the chest",
+                    _ => return,
+                };
+
+                (text.to_string(), WHITE)
+            }
+            Event::LevelledUp(level) => (format!("welcome to level {level}!"), YELLOW),
+            Event::TrapTriggered(id, kind) => {
+                if !self.is_noticed(id) {
+                    return;
+                }
+
+                let verb = if id == self.player { "step" } else { "steps" };
+                (format!("{} {} on a {}", self.name_of(id), verb, kind.name()), RED)
+            }
+            Event::TrapFound(id, kind) if id == self.player => (format!("you find a {}", kind.name()), YELLOW),
+            Event::SecretDoorFound(id) if id == self.player => ("you find a secret door".to_string(), YELLOW),
+            Event::Received(item) => match item.count {
+                1 => (format!("you receive the {}", self.item_name(item.kind)), GREEN),
+                count => (format!("you receive {} x{}", self.item_name(item.kind), count), GREEN),
+            },
+            Event::ReputationChanged { faction, hostile: true } => (format!("the {} are now hostile", faction.name()), RED),
+            Event::ReputationChanged { faction, hostile: false } => {
+                (format!("the {} are no longer hostile", faction.name()), GREEN)
+            }
+            Event::AbilityUsed(id, kind) => {
+                if !self.is_noticed(id) {
+                    return;
+                }
+
+                let verb = if id == self.player { "use" } else { "uses" };
+                (format!("{} {} {}", self.name_of(id), verb, kind.definition().name), WHITE)
+            }
+            Event::Bought(kind, price) => (format!("you buy the {} for {} gold", self.item_name(kind), price), WHITE),
+            Event::Sold(kind, price) => (format!("you sell the {} for {} gold", self.item_name(kind), price), WHITE),
+            Event::HandedOver(item) => match item.count {
+                1 => (format!("you hand over the {}", self.item_name(item.kind)), WHITE),
+                count => (format!("you hand over {} x{}", self.item_name(item.kind), count), WHITE),
+            },
+            Event::Identified(kind) => {
+                let appearance = self.appearance_name(kind).unwrap_or_default();
+                (format!("the {} is a {}", appearance, kind.name()), YELLOW)
+            }
+            _ => return,
+        };
+
+        self.log.push(capitalize(&text), color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::combat::AttackOutcome;
+    use crate::ecs::EntityId;
+    use crate::world::World;
+
+    use super::Event;
+    use super::MessageLog;
+    use super::RED;
+    use super::WHITE;
+
+    #[test]
+    fn repeats_merge() {
+        let mut log = MessageLog::default();
+        log.push("You hit the rat".to_string(), WHITE);
+        log.push("You hit the rat".to_string(), WHITE);
+        log.push("You hit the rat".to_string(), WHITE);
+        log.push("The rat hits you".to_string(), RED);
+        log.push("You hit the rat".to_string(), WHITE);
+
+        let lines: Vec<String> = log.lines(10).iter().map(|message| message.display()).collect();
+        assert_eq!(lines, vec!["You hit the rat x3", "The rat hits you", "You hit the rat"]);
+    }
+
+    #[test]
+    fn scrollback() {
+        let mut log = MessageLog::default();
+        for i in 0..10 {
+            log.push(format!("{i}"), WHITE);
+        }
+
+        let text = |log: &MessageLog| log.lines(3).iter().map(|message| message.display()).collect::<Vec<_>>();
+        assert_eq!(text(&log), vec!["7", "8", "9"]);
+
+        log.scroll(2);
+        assert_eq!(text(&log), vec!["5", "6", "7"]);
+        log.push("10".to_string(), WHITE);
+        assert_eq!(text(&log), vec!["5", "6", "7"]);
+
+        log.scroll(100);
+        assert_eq!(text(&log), vec!["0"]);
+        log.scroll(-100);
+        assert_eq!(text(&log), vec!["8", "9", "10"]);
+    }
+
+    #[test]
+    fn events_are_formatted() {
+        let mut world = World::default();
+        let rat = EntityId(1);
+        world.names.insert(rat, "rat".to_string());
+
+        world.notify(Event::Attacked { attacker: world.player, defender: rat, outcome: AttackOutcome::Hit(2) });
+        world.notify(Event::Attacked { attacker: rat, defender: world.player, outcome: AttackOutcome::Miss });
+        world.notify(Event::Killed(rat));
+
+        let lines: Vec<String> = world.log.lines(10).iter().map(|message| message.display()).collect();
+        assert_eq!(lines, vec!["You hit the rat", "The rat misses you"]);
+    }
+}
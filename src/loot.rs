@@ -0,0 +1,278 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::item::Affix;
+use crate::item::Item;
+use crate::item::ItemKind;
+use crate::rng::Rng;
+use crate::rng::Stream;
+use crate::spawn::MonsterKind;
+use crate::world::World;
+
+/// The most affixes a single item can roll
+const MAX_AFFIXES: usize = 2;
+
+/// Which table an entity's drops are rolled from, usually decided by what kind of monster it is
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum LootTable {
+    Beast,
+    Humanoid,
+    Chest,
+    /// Items lying around a level when it's first entered
+    Floor,
+    /// What a shopkeeper has for sale
+    Shop,
+}
+
+/// What a monster drops when it dies
+///
+/// Its rolls are keyed on where it was spawned and what it is rather than on its id, so spawning
+/// anything else first doesn't change what it drops.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Loot {
+    pub table: LootTable,
+    /// The tile the monster was spawned on
+    pub origin: [i32; 2],
+    pub kind: MonsterKind,
+}
+
+impl Loot {
+    /// Get the keys the monster's drops are rolled from
+    pub fn source(&self) -> [u64; 3] {
+        [self.origin[0] as u64, self.origin[1] as u64, self.kind as u64]
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LootEntry {
+    pub kind: ItemKind,
+    pub weight: u32,
+    /// The shallowest depth at which the entry can drop
+    pub min_depth: u32,
+    /// The inclusive range of how many items drop together
+    pub count: [u32; 2],
+}
+
+const BEAST: &[LootEntry] = &[
+    LootEntry { kind: ItemKind::Rock, weight: 4, min_depth: 0, count: [1, 3] },
+    LootEntry { kind: ItemKind::HealthPotion, weight: 2, min_depth: 0, count: [1, 1] },
+];
+
+const HUMANOID: &[LootEntry] = &[
+    LootEntry { kind: ItemKind::Arrow, weight: 8, min_depth: 0, count: [3, 10] },
+    LootEntry { kind: ItemKind::HealthPotion, weight: 6, min_depth: 0, count: [1, 2] },
+    LootEntry { kind: ItemKind::Dagger, weight: 4, min_depth: 0, count: [1, 1] },
+    LootEntry { kind: ItemKind::Torch, weight: 4, min_depth: 0, count: [1, 1] },
+    LootEntry { kind: ItemKind::LeatherArmor, weight: 3, min_depth: 1, count: [1, 1] },
+    LootEntry { kind: ItemKind::RingOfSwiftness, weight: 1, min_depth: 3, count: [1, 1] },
+    LootEntry { kind: ItemKind::Gold, weight: 8, min_depth: 0, count: [2, 15] },
+    LootEntry { kind: ItemKind::ScrollOfTeleport, weight: 2, min_depth: 1, count: [1, 1] },
+];
+
+const CHEST: &[LootEntry] = &[
+    LootEntry { kind: ItemKind::Arrow, weight: 4, min_depth: 0, count: [5, 20] },
+    LootEntry { kind: ItemKind::HealthPotion, weight: 4, min_depth: 0, count: [1, 3] },
+    LootEntry { kind: ItemKind::Dagger, weight: 2, min_depth: 0, count: [1, 1] },
+    LootEntry { kind: ItemKind::LeatherArmor, weight: 2, min_depth: 0, count: [1, 1] },
+    LootEntry { kind: ItemKind::RingOfSwiftness, weight: 1, min_depth: 2, count: [1, 1] },
+    LootEntry { kind: ItemKind::Bomb, weight: 2, min_depth: 1, count: [1, 2] },
+    LootEntry { kind: ItemKind::Gold, weight: 4, min_depth: 0, count: [10, 40] },
+    LootEntry { kind: ItemKind::SpeedPotion, weight: 2, min_depth: 1, count: [1, 2] },
+    LootEntry { kind: ItemKind::ScrollOfIdentify, weight: 2, min_depth: 0, count: [1, 2] },
+];
+
+const FLOOR: &[LootEntry] = &[
+    LootEntry { kind: ItemKind::Arrow, weight: 4, min_depth: 0, count: [2, 8] },
+    LootEntry { kind: ItemKind::Rock, weight: 4, min_depth: 0, count: [1, 3] },
+    LootEntry { kind: ItemKind::HealthPotion, weight: 3, min_depth: 0, count: [1, 1] },
+    LootEntry { kind: ItemKind::Torch, weight: 2, min_depth: 0, count: [1, 1] },
+    LootEntry { kind: ItemKind::Dagger, weight: 1, min_depth: 1, count: [1, 1] },
+    LootEntry { kind: ItemKind::Bomb, weight: 1, min_depth: 2, count: [1, 1] },
+    LootEntry { kind: ItemKind::LeatherArmor, weight: 1, min_depth: 3, count: [1, 1] },
+    LootEntry { kind: ItemKind::Gold, weight: 3, min_depth: 0, count: [3, 12] },
+    LootEntry { kind: ItemKind::SpeedPotion, weight: 1, min_depth: 1, count: [1, 1] },
+    LootEntry { kind: ItemKind::ScrollOfIdentify, weight: 2, min_depth: 0, count: [1, 1] },
+    LootEntry { kind: ItemKind::ScrollOfTeleport, weight: 1, min_depth: 1, count: [1, 1] },
+];
+
+const SHOP: &[LootEntry] = &[
+    LootEntry { kind: ItemKind::Arrow, weight: 4, min_depth: 0, count: [10, 30] },
+    LootEntry { kind: ItemKind::HealthPotion, weight: 4, min_depth: 0, count: [1, 3] },
+    LootEntry { kind: ItemKind::Torch, weight: 2, min_depth: 0, count: [1, 2] },
+    LootEntry { kind: ItemKind::Dagger, weight: 2, min_depth: 0, count: [1, 1] },
+    LootEntry { kind: ItemKind::LeatherArmor, weight: 2, min_depth: 0, count: [1, 1] },
+    LootEntry { kind: ItemKind::Bomb, weight: 2, min_depth: 1, count: [1, 3] },
+    LootEntry { kind: ItemKind::RingOfSwiftness, weight: 1, min_depth: 2, count: [1, 1] },
+    LootEntry { kind: ItemKind::SpeedPotion, weight: 2, min_depth: 1, count: [1, 2] },
+    LootEntry { kind: ItemKind::ScrollOfIdentify, weight: 3, min_depth: 0, count: [1, 3] },
+    LootEntry { kind: ItemKind::ScrollOfTeleport, weight: 2, min_depth: 0, count: [1, 2] },
+];
+
+impl LootTable {
+    /// Get the number of times a specific table is rolled and the weight of rolling nothing
+    pub fn rolls(self) -> (u32, u32) {
+        match self {
+            LootTable::Beast => (1, 6),
+            LootTable::Humanoid => (2, 10),
+            LootTable::Chest => (3, 0),
+            LootTable::Floor => (1, 0),
+            LootTable::Shop => (6, 0),
+        }
+    }
+
+    pub fn entries(self) -> &'static [LootEntry] {
+        match self {
+            LootTable::Beast => BEAST,
+            LootTable::Humanoid => HUMANOID,
+            LootTable::Chest => CHEST,
+            LootTable::Floor => FLOOR,
+            LootTable::Shop => SHOP,
+        }
+    }
+
+    /// Roll the items dropped at a specific depth
+    pub fn generate(self, rng: &mut Rng, depth: u32) -> Vec<Item> {
+        let (rolls, nothing) = self.rolls();
+        let entries: Vec<&LootEntry> = self.entries().iter().filter(|entry| entry.min_depth <= depth).collect();
+        let total = nothing + entries.iter().map(|entry| entry.weight).sum::<u32>();
+
+        let mut items = vec![];
+        for _ in 0..rolls {
+            let mut roll = rng.range(0..total as i32) as u32;
+            let entry = entries.iter().find(|entry| {
+                if roll < entry.weight {
+                    return true;
+                }
+
+                roll -= entry.weight;
+                false
+            });
+
+            if let Some(entry) = entry {
+                let count = rng.range(entry.count[0] as i32..entry.count[1] as i32 + 1) as u32;
+                let mut item = Item::new(entry.kind, count);
+                item.affixes = roll_affixes(rng, entry.kind, depth);
+                items.push(item);
+            }
+        }
+
+        items
+    }
+}
+
+/// Roll the affixes of an item, which grow more likely the deeper it's found
+fn roll_affixes(rng: &mut Rng, kind: ItemKind, depth: u32) -> Vec<Affix> {
+    let slot = match kind.slot() {
+        Some(slot) => slot,
+        None => return vec![],
+    };
+
+    let chance = (0.1 + 0.05 * depth as f32).min(0.6);
+    let mut affixes = vec![];
+    while affixes.len() < MAX_AFFIXES && rng.chance(chance) {
+        let candidates: Vec<Affix> = Affix::ALL
+            .iter()
+            .copied()
+            .filter(|affix| affix.applies_to(slot) && !affixes.contains(affix))
+            .collect();
+
+        if candidates.is_empty() {
+            break;
+        }
+
+        affixes.push(candidates[rng.range(0..candidates.len() as i32) as usize]);
+    }
+
+    affixes
+}
+
+impl World {
+    /// Roll a loot table and place whatever drops at a position
+    ///
+    /// `source` identifies whatever the loot comes from, such as `Loot::source` or a chest's tile.
+    /// The rolls only depend on the world's seed, its depth and the source, so the same world
+    /// always drops the same loot from the same thing no matter where or when it happens.
+    pub fn drop_loot(&mut self, table: LootTable, position: [i32; 2], source: &[u64]) {
+        let keys: Vec<u64> = std::iter::once(self.depth as u64).chain(source.iter().copied()).collect();
+        let mut rng = self.derive_rng(Stream::Loot, &keys);
+
+        for item in table.generate(&mut rng, self.depth) {
+            self.spawn_item(item, position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::combat::DamageSource;
+    use crate::item::ItemKind;
+    use crate::rng::Rng;
+    use crate::spawn::MonsterKind;
+    use crate::world::World;
+
+    use super::LootTable;
+
+    #[test]
+    fn generation_is_seeded() {
+        let items = |seed, position: [i32; 2]| {
+            let mut rng = Rng::from_keys(&[seed, 2, position[0] as u64, position[1] as u64]);
+            LootTable::Humanoid.generate(&mut rng, 2)
+        };
+
+        assert_eq!(items(1, [4, 5]), items(1, [4, 5]));
+        assert!((0..20).any(|x| items(1, [x, 5]) != items(2, [x, 5])));
+    }
+
+    #[test]
+    fn depth_and_affixes() {
+        let mut rng = Rng::new(9);
+        for _ in 0..500 {
+            for item in LootTable::Humanoid.generate(&mut rng, 0) {
+                assert!(item.kind != ItemKind::LeatherArmor && item.kind != ItemKind::RingOfSwiftness);
+                assert!(item.affixes.is_empty() || item.kind.slot().is_some());
+            }
+        }
+
+        let deep: Vec<_> = (0..500).flat_map(|_| LootTable::Humanoid.generate(&mut rng, 10)).collect();
+        assert!(deep.iter().any(|item| item.kind == ItemKind::RingOfSwiftness));
+        assert!(deep.iter().any(|item| !item.affixes.is_empty()));
+    }
+
+    #[test]
+    fn death_drops_loot() {
+        let drops = |seed| {
+            let mut world = World { seed, ..Default::default() };
+            for x in 0..20 {
+                let monster = world.spawn_monster(MonsterKind::Goblin, [x, 0]);
+                world.damage(monster, 100, DamageSource::Entity(world.player));
+            }
+
+            world.items.values().cloned().collect::<Vec<_>>()
+        };
+
+        assert!(!drops(3).is_empty());
+        assert_eq!(drops(3), drops(3));
+    }
+
+    #[test]
+    fn drops_depend_on_where_monsters_spawned() {
+        let drops = |world: &mut World| {
+            (0..20)
+                .map(|x| {
+                    let monster = world.spawn_monster(MonsterKind::Goblin, [x, 0]);
+                    world.damage(monster, 100, DamageSource::Entity(world.player));
+                    world.items_at([x, 0]).iter().map(|id| world.items[id].clone()).collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let first = drops(&mut World { seed: 5, ..Default::default() });
+        assert!(first.iter().any(|items| *items != first[0]));
+
+        // Spawning something else first shifts every id, but not what the monsters drop
+        let mut busier = World { seed: 5, ..Default::default() };
+        busier.spawn_monster(MonsterKind::Rat, [0, 5]);
+        assert_eq!(drops(&mut busier), first);
+        assert_ne!(drops(&mut World { seed: 6, ..Default::default() }), first);
+    }
+}
@@ -1,30 +1,91 @@
+mod ability;
+mod action;
+mod ai;
 mod camera;
 mod chunk;
+mod combat;
+mod dialogue;
 mod ecs;
 mod graphics;
 mod entity;
+mod equipment;
 mod error;
+mod faction;
+mod fov;
+mod health;
+mod identify;
+mod interaction;
+mod inventory;
+mod item;
+mod level;
 mod light;
+mod line;
+mod log;
+mod loot;
 mod material;
+mod morgue;
+mod pathfinding;
 mod tile;
 mod time;
 mod player;
+mod progression;
+mod projectile;
+mod rng;
+mod scheduler;
+mod shop;
+mod spawn;
+mod status;
+mod stealth;
+mod terrain;
+mod trap;
 mod world;
 
+use std::path::Path;
+
+use gilrs::Button;
+use gilrs::EventType;
+use gilrs::Gilrs;
 use num_traits::ToPrimitive;
 use tracing::info;
 use winit::dpi::PhysicalSize;
+use winit::event::ElementState;
 use winit::event::Event;
+use winit::event::KeyboardInput;
+use winit::event::VirtualKeyCode;
 use winit::event::WindowEvent;
 use winit::event_loop::ControlFlow;
 use winit::event_loop::EventLoop;
 use winit::window::WindowBuilder;
 
+use crate::ability::Abilities;
+use crate::ability::AbilityKind;
+use crate::action::Action;
+use crate::action::ActionQueue;
+use crate::combat::Attack;
+use crate::combat::Defense;
+use crate::dialogue::Dialogue;
+use crate::ecs::EntityId;
 use crate::ecs::Resolution;
 use crate::entity::Entity;
+use crate::equipment::Equipment;
 use crate::error::Error;
+use crate::faction::Faction;
 use crate::graphics::Graphics;
+use crate::graphics::HUD_ROWS;
+use crate::health::Health;
+use crate::interaction::Lever;
+use crate::inventory::Inventory;
+use crate::item::Item;
+use crate::item::ItemKind;
+use crate::level::Layout;
+use crate::level::Rect;
 use crate::light::Light;
+use crate::progression::Progression;
+use crate::projectile::Ranged;
+use crate::scheduler::Energy;
+use crate::scheduler::Scheduler;
+use crate::scheduler::SimulationMode;
+use crate::spawn::MonsterKind;
 use crate::tile::Tile;
 use crate::time::Time;
 use crate::world::World;
@@ -41,13 +102,15 @@ async fn main() -> Result<(), Error> {
         .build(&event_loop)?;
     let mut scale_factor = window.scale_factor();
 
-    info!("Creating graphics instance");
-    let mut graphics = Graphics::new(&window, resolution).await?;
-
     info!("Creating test world");
-    let world = World {
+    let mut world = World {
         name: "World".to_string(),
         seed: 0,
+        depth: 1,
+        mode: SimulationMode::TurnBased,
+        player: EntityId(0),
+        next_entity_id: 1,
+        width: 4,
         chunks: vec![
             [
                 [
@@ -134,21 +197,81 @@ async fn main() -> Result<(), Error> {
             [[ToPrimitive::to_u8(&Tile::Wall).unwrap(); 16]; 16],
             [[ToPrimitive::to_u8(&Tile::Wall).unwrap(); 16]; 16],
         ],
-        entities: vec![
-            Entity::new([0.0, 0.0], [0, 0], [1, 1], u32::MAX, None),
-        ],
         lights: vec![
             Light::new([-0.5, 0.5], [255, 0, 0], 255),
             Light::new([1.0, 0.0], [0, 255, 0], 255),
             Light::new([0.0, -1.0], [0, 0, 255], 255),
             Light::new([0.5, -0.5], [255, 255, 255], 255),
         ],
+        ..Default::default()
     };
+    world.entities.insert(world.player, Entity::new([0.0, 0.0], [0, 0], [1, 1], u32::MAX, None));
+    world.energies.insert(world.player, Energy::default());
+    world.healths.insert(world.player, Health::new(20));
+    world.attacks.insert(world.player, Attack { damage: 3, ..Default::default() });
+    world.defenses.insert(world.player, Defense { armor: 1, evasion: 10 });
+    world.inventories.insert(world.player, Inventory::new(10, 100));
+    world.equipment.insert(world.player, Equipment::default());
+    world.progressions.insert(world.player, Progression::default());
+    world.inventories.get_mut(&world.player).unwrap().add(Item::new(ItemKind::Torch, 1)).unwrap();
+    world.equip(world.player, 0);
+    world.inventories.get_mut(&world.player).unwrap().add(Item::new(ItemKind::Bomb, 2)).unwrap();
+    world.inventories.get_mut(&world.player).unwrap().add(Item::new(ItemKind::Gold, 30)).unwrap();
+    world.ranged.insert(world.player, Ranged {
+        attack: Attack { damage: 2, accuracy: 70, critical_chance: 10, effect: None },
+        range: 10,
+        speed: 2,
+        atlas_position: [3, 0],
+        color: u32::MAX,
+    });
+    world.abilities.insert(world.player, Abilities::new(vec![
+        AbilityKind::PoisonDart,
+        AbilityKind::Firebolt,
+        AbilityKind::ConeOfCold,
+        AbilityKind::Fireball,
+    ]));
+    world.move_entity(world.player, [0, 8]);
+
+    world.spawn_monster(MonsterKind::Goblin, [12, 8]);
+
+    let elder = world.spawn();
+    world.names.insert(elder, "elder".to_string());
+    world.factions.insert(elder, Faction::Townsfolk);
+    world.entities.insert(elder, Entity::new([0.0, 0.0], [0, 0], [1, 1], 0xffff_c080, None));
+    world.dialogues.insert(elder, Dialogue::load(Path::new("dialogue/elder.json"))?);
+    world.move_entity(elder, [2, 7]);
+    world.spawn_shopkeeper([6, 9]);
+
+    world.spawn_item(Item::new(ItemKind::HealthPotion, 2), [4, 8]);
+    world.spawn_item(Item::new(ItemKind::Dagger, 1), [5, 8]);
+    world.spawn_item(Item::new(ItemKind::ScrollOfIdentify, 1), [6, 8]);
+
+    world.set_tile([9, 8], Tile::DoorClosed);
+    world.set_tile([3, 10], Tile::LeverOff);
+    for position in [[9, 7], [9, 9]] {
+        world.set_tile(position, Tile::DoorClosed);
+    }
+    world.levers.push(Lever { position: [3, 10], targets: vec![[9, 7], [9, 9]] });
+    world.set_tile([14, 9], Tile::Chest);
+    world.place_hidden_features(4, 1);
+    world.populate(&Layout {
+        rooms: vec![Rect::new([0, 7], [16, 3])],
+        reserved: vec![Rect::new([13, 7], [3, 3])],
+        start: [0, 8],
+    });
+    world.update_visibility();
     info!("Created test world");
 
+    info!("Creating graphics instance");
+    let mut graphics = Graphics::new(&window, resolution, world.width, world.chunks.len()).await?;
     graphics.write_chunks(&world.chunks);
 
     let mut time = Time::new();
+    let mut scheduler = Scheduler::new();
+    let mut actions = ActionQueue::new();
+    let mut digging = false;
+    let mut hud = vec![];
+    let mut gamepads = Gilrs::new()?;
 
     info!("Entering event loop");
     event_loop.run(move |event, _, control_flow| {
@@ -162,9 +285,202 @@ async fn main() -> Result<(), Error> {
                     scale_factor = sf;
                     resolution = (*new_inner_size).into();
                 },
+                WindowEvent::KeyboardInput {
+                    input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(key), .. },
+                    ..
+                } => {
+                    // Z only applies to the very next key, whatever it turns out to be
+                    let was_digging = std::mem::take(&mut digging);
+
+                    // While aiming, trading or talking the keyboard only drives their panels
+                    if world.targeting.is_some() {
+                        match key {
+                            VirtualKeyCode::Up => world.move_cursor([0, -1]),
+                            VirtualKeyCode::Down => world.move_cursor([0, 1]),
+                            VirtualKeyCode::Left => world.move_cursor([-1, 0]),
+                            VirtualKeyCode::Right => world.move_cursor([1, 0]),
+                            VirtualKeyCode::Return | VirtualKeyCode::Space => {
+                                let targeting = world.targeting.take().unwrap();
+                                if !actions.is_pending(world.player) && !world.is_dead() {
+                                    actions.push(world.player, Action::Ability(targeting.ability, targeting.cursor));
+                                }
+                            }
+                            VirtualKeyCode::Escape => world.targeting = None,
+                            _ => (),
+                        }
+
+                        return;
+                    }
+
+                    if world.trade.is_some() {
+                        match key {
+                            VirtualKeyCode::Up => world.select_offer(-1),
+                            VirtualKeyCode::Down => world.select_offer(1),
+                            VirtualKeyCode::Tab => world.switch_trade_mode(),
+                            VirtualKeyCode::Return | VirtualKeyCode::Space => world.confirm_offer(),
+                            VirtualKeyCode::Escape => world.trade = None,
+                            _ => (),
+                        }
+
+                        return;
+                    }
+
+                    if world.conversation.is_some() {
+                        match key {
+                            VirtualKeyCode::Up => world.select_choice(-1),
+                            VirtualKeyCode::Down => world.select_choice(1),
+                            VirtualKeyCode::Return | VirtualKeyCode::Space => world.confirm_choice(),
+                            VirtualKeyCode::Escape => world.conversation = None,
+                            _ => (),
+                        }
+
+                        return;
+                    }
+
+                    let action = match key {
+                        VirtualKeyCode::Up => Some(Action::Move([0, -1])),
+                        VirtualKeyCode::Down => Some(Action::Move([0, 1])),
+                        VirtualKeyCode::Left => Some(Action::Move([-1, 0])),
+                        VirtualKeyCode::Right => Some(Action::Move([1, 0])),
+                        VirtualKeyCode::Space => Some(Action::Wait),
+                        VirtualKeyCode::F => world.nearest_visible_hostile().map(Action::Fire),
+                        VirtualKeyCode::G => Some(Action::PickUp),
+                        VirtualKeyCode::D => Some(Action::Drop(0)),
+                        VirtualKeyCode::Key1 => Some(Action::Use(0)),
+                        VirtualKeyCode::Key2 => Some(Action::Use(1)),
+                        VirtualKeyCode::Key3 => Some(Action::Use(2)),
+                        VirtualKeyCode::E => Some(Action::Equip(0)),
+                        VirtualKeyCode::S => Some(Action::Search),
+                        VirtualKeyCode::Q | VirtualKeyCode::W | VirtualKeyCode::R | VirtualKeyCode::T => {
+                            let index = match key {
+                                VirtualKeyCode::Q => 0,
+                                VirtualKeyCode::W => 1,
+                                VirtualKeyCode::R => 2,
+                                _ => 3,
+                            };
+
+                            world.start_targeting(index);
+                            None
+                        }
+                        VirtualKeyCode::Z => {
+                            digging = true;
+                            None
+                        }
+                        VirtualKeyCode::PageUp => {
+                            world.log.scroll(1);
+                            None
+                        }
+                        VirtualKeyCode::PageDown => {
+                            world.log.scroll(-1);
+                            None
+                        }
+                        VirtualKeyCode::C => world
+                            .positions
+                            .get(&world.player)
+                            .and_then(|position| world.adjacent_tile(*position, |tile| tile == Tile::DoorOpen))
+                            .map(Action::Interact),
+                        _ => None,
+                    };
+
+                    // Function keys pick from the perks on offer after levelling up
+                    let perk = match key {
+                        VirtualKeyCode::F1 => Some(0),
+                        VirtualKeyCode::F2 => Some(1),
+                        VirtualKeyCode::F3 => Some(2),
+                        VirtualKeyCode::F4 => Some(3),
+                        VirtualKeyCode::F5 => Some(4),
+                        VirtualKeyCode::F6 => Some(5),
+                        VirtualKeyCode::F7 => Some(6),
+                        _ => None,
+                    };
+
+                    if let Some(index) = perk {
+                        let choices = world.progressions.get(&world.player).map(|p| p.choices()).unwrap_or_default();
+                        if let Some(perk) = choices.get(index) {
+                            world.pick_perk(world.player, *perk);
+                        }
+                    }
+
+                    // Z turns the next move into digging in that direction
+                    let action = match action {
+                        Some(Action::Move(direction)) if was_digging => world
+                            .positions
+                            .get(&world.player)
+                            .map(|position| Action::Dig([position[0] + direction[0], position[1] + direction[1]])),
+                        action => action,
+                    };
+
+                    // Only hold on to one action so held keys don't queue up a backlog of moves
+                    if let Some(action) = action {
+                        if !actions.is_pending(world.player) && !world.is_dead() {
+                            actions.push(world.player, action);
+                        }
+                    }
+                },
                 _ => (),
             },
             Event::MainEventsCleared => {
+                while let Some(gilrs::Event { event, .. }) = gamepads.next_event() {
+                    if world.trade.is_some() {
+                        match event {
+                            EventType::ButtonPressed(Button::DPadUp, _) => world.select_offer(-1),
+                            EventType::ButtonPressed(Button::DPadDown, _) => world.select_offer(1),
+                            EventType::ButtonPressed(Button::North, _) => world.switch_trade_mode(),
+                            EventType::ButtonPressed(Button::South, _) => world.confirm_offer(),
+                            EventType::ButtonPressed(Button::East, _) => world.trade = None,
+                            _ => (),
+                        }
+
+                        continue;
+                    }
+
+                    if world.conversation.is_none() {
+                        continue;
+                    }
+
+                    match event {
+                        EventType::ButtonPressed(Button::DPadUp, _) => world.select_choice(-1),
+                        EventType::ButtonPressed(Button::DPadDown, _) => world.select_choice(1),
+                        EventType::ButtonPressed(Button::South, _) => world.confirm_choice(),
+                        EventType::ButtonPressed(Button::East, _) => world.conversation = None,
+                        _ => (),
+                    }
+                }
+
+                // Death is permanent, so the world stops once the player dies and the run is written up
+                if !world.is_dead() {
+                    scheduler.update(&mut world, &mut actions, time.delta_time(), ai::think);
+                    if world.is_dead() {
+                        actions.clear();
+                        match world.write_morgue(Path::new("./morgue")) {
+                            Ok(path) => info!("Wrote morgue file to {}", path.display()),
+                            Err(e) => tracing::error!("Failed to write morgue file: {e}"),
+                        }
+                    }
+                }
+
+                for index in std::mem::take(&mut world.dirty_chunks) {
+                    graphics.write_chunk(index, &world.chunks[index]);
+                }
+
+                if world.update_visibility() {
+                    graphics.write_visibility(&world.visibility_masks());
+                }
+
+                let mut sprites = world.visible_sprites();
+                sprites.extend(world.targeting_sprites());
+                graphics.write_entities(&sprites);
+
+                let lines = world
+                    .targeting_lines(HUD_ROWS)
+                    .or_else(|| world.trade_lines(HUD_ROWS))
+                    .or_else(|| world.dialogue_lines())
+                    .unwrap_or_else(|| world.hud_lines(HUD_ROWS));
+                if lines != hud {
+                    graphics.write_hud(&lines);
+                    hud = lines;
+                }
+
                 if let Err(e) = graphics.render(resolution) {
                     tracing::error!("{e}");
                     *control_flow = ControlFlow::Exit;
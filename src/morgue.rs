@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat::DamageSource;
+use crate::error::Error;
+use crate::item::Item;
+use crate::world::World;
+
+/// The number of log messages copied into a morgue file
+pub const MORGUE_MESSAGES: usize = 10;
+
+/// What happened over the course of a run, kept for the morgue file
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RunStats {
+    /// How many of each kind of entity the player killed, by name
+    pub kills: BTreeMap<String, u32>,
+    /// Set once the player has died, after which the run is over for good
+    pub cause_of_death: Option<String>,
+}
+
+/// Describe an item and how many there are, as in "arrow x12"
+fn describe_item(item: &Item) -> String {
+    let mut text = item.kind.name().to_string();
+    for affix in &item.affixes {
+        text.push_str(&format!(" ({affix:?})").to_lowercase());
+    }
+
+    if item.count > 1 {
+        text.push_str(&format!(" x{}", item.count));
+    }
+
+    text
+}
+
+impl World {
+    /// Whether the player has died, which ends the run
+    ///
+    /// A dead world is still saved as it was, but can't be played any further.
+    pub fn is_dead(&self) -> bool {
+        self.stats.cause_of_death.is_some()
+    }
+
+    /// Describe what killed the player
+    pub fn describe_death(&self, source: DamageSource) -> String {
+        match source {
+            DamageSource::Entity(id) => format!("killed by {}", self.name_of(id)),
+            DamageSource::Status(kind) => format!("died while {}", kind.definition().adjective),
+            DamageSource::Explosion(Some(id)) if id != self.player => format!("blown up by {}", self.name_of(id)),
+            DamageSource::Explosion(_) => "blown up".to_string(),
+            DamageSource::Trap(kind) => format!("killed by a {}", kind.name()),
+        }
+    }
+
+    /// Write up a summary of the run
+    pub fn morgue(&self) -> String {
+        let mut text = String::new();
+        let cause = self.stats.cause_of_death.as_deref().unwrap_or("still alive");
+        let _ = writeln!(text, "{} (seed {})", self.name, self.seed);
+        let _ = writeln!(text, "Depth {}: {}", self.depth, cause);
+        if let Some(progression) = self.progressions.get(&self.player) {
+            let _ = writeln!(text, "Level {} ({} XP)", progression.level, progression.experience);
+        }
+
+        let _ = writeln!(text, "\nKills:");
+        if self.stats.kills.is_empty() {
+            let _ = writeln!(text, "  none");
+        }
+        for (name, count) in &self.stats.kills {
+            let _ = writeln!(text, "  {count} {name}");
+        }
+
+        let _ = writeln!(text, "\nEquipment:");
+        if let Some(equipment) = self.equipment.get(&self.player) {
+            for (slot, item) in &equipment.slots {
+                let _ = writeln!(text, "  {:?}: {}", slot, describe_item(item));
+            }
+        }
+
+        let _ = writeln!(text, "\nInventory:");
+        if let Some(inventory) = self.inventories.get(&self.player) {
+            for item in &inventory.items {
+                let _ = writeln!(text, "  {}", describe_item(item));
+            }
+        }
+
+        let _ = writeln!(text, "\nLast messages:");
+        for message in self.log.lines(MORGUE_MESSAGES) {
+            let _ = writeln!(text, "  {}", message.display());
+        }
+
+        text
+    }
+
+    /// Write the morgue file into a directory, returning its path
+    pub fn write_morgue(&self, directory: &Path) -> Result<PathBuf, Error> {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default();
+        let path = directory.join(format!("morgue-{}-{}.txt", self.seed, time));
+        std::fs::create_dir_all(directory)?;
+        std::fs::write(&path, self.morgue())?;
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::combat::DamageSource;
+    use crate::health::Health;
+    use crate::inventory::Inventory;
+    use crate::item::Item;
+    use crate::item::ItemKind;
+    use crate::status::StatusKind;
+    use crate::world::World;
+
+    fn world() -> World {
+        let mut world = World { name: "Test".to_string(), seed: 7, depth: 3, ..Default::default() };
+        let player = world.player;
+        world.healths.insert(player, Health::new(5));
+        world.inventories.insert(player, Inventory::new(4, 100));
+        world.inventories.get_mut(&player).unwrap().add(Item::new(ItemKind::Arrow, 12)).unwrap();
+        world
+    }
+
+    #[test]
+    fn death_ends_the_run() {
+        let mut world = world();
+        let (player, rat) = (world.player, world.spawn());
+        world.names.insert(rat, "rat".to_string());
+        world.healths.insert(rat, Health::new(1));
+
+        world.damage(rat, 1, DamageSource::Entity(player));
+        assert_eq!(world.stats.kills["rat"], 1);
+        assert!(!world.is_dead());
+
+        world.damage(player, 5, DamageSource::Status(StatusKind::Poison));
+        world.damage(player, 5, DamageSource::Explosion(None));
+        assert!(world.is_dead());
+        assert_eq!(world.stats.cause_of_death.as_deref(), Some("died while poisoned"));
+        assert!(world.inventories.contains_key(&player));
+
+        let saved = serde_json::to_string(&world).unwrap();
+        let loaded: World = serde_json::from_str(&saved).unwrap();
+        assert!(loaded.is_dead());
+    }
+
+    #[test]
+    fn morgue_summarises_the_run() {
+        let mut world = world();
+        let goblin = world.spawn();
+        world.names.insert(goblin, "goblin".to_string());
+        world.damage(world.player, 10, DamageSource::Entity(goblin));
+
+        let morgue = world.morgue();
+        assert!(morgue.contains("Test (seed 7)"));
+        assert!(morgue.contains("Depth 3: killed by the goblin"));
+        assert!(morgue.contains("arrow x12"));
+        assert!(morgue.contains("You die..."));
+    }
+}
@@ -0,0 +1,315 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+
+use crate::world::World;
+
+/// The cost of a single orthogonal step across a tile with a movement cost of 1
+pub const CARDINAL_COST: u32 = 10;
+/// The cost of a single diagonal step across a tile with a movement cost of 1
+pub const DIAGONAL_COST: u32 = 14;
+
+const DIRECTIONS: [[i32; 2]; 8] = [
+    [0, -1],
+    [1, 0],
+    [0, 1],
+    [-1, 0],
+    [1, -1],
+    [1, 1],
+    [-1, 1],
+    [-1, -1],
+];
+
+/// When a diagonal step may squeeze past impassable tiles
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CornerCutting {
+    /// Both orthogonal neighbours must be passable
+    Never,
+    /// At least one orthogonal neighbour must be passable
+    IfOneOpen,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct PathOptions {
+    pub corner_cutting: CornerCutting,
+    /// The number of tiles that may be expanded before giving up
+    pub search_limit: usize,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        Self {
+            corner_cutting: CornerCutting::Never,
+            search_limit: 4096,
+        }
+    }
+}
+
+impl World {
+    /// Get the cost of walking across a tile, or `None` if it can't be walked on
+    pub fn movement_cost(&self, position: [i32; 2]) -> Option<u32> {
+        self.tile(position).filter(|tile| !tile.is_solid()).map(|tile| tile.movement_cost())
+    }
+}
+
+/// Iterate over the tiles reachable in one step along with the cost of stepping there
+fn neighbours<F>(position: [i32; 2], options: &PathOptions, cost: &F) -> impl Iterator<Item = ([i32; 2], u32)>
+where
+    F: Fn([i32; 2]) -> Option<u32>,
+{
+    let [x, y] = position;
+    let mut steps = Vec::with_capacity(DIRECTIONS.len());
+    for [dx, dy] in DIRECTIONS {
+        let target = [x + dx, y + dy];
+        let tile_cost = match cost(target) {
+            Some(tile_cost) => tile_cost,
+            None => continue,
+        };
+
+        if dx != 0 && dy != 0 {
+            let open = [[x + dx, y], [x, y + dy]].iter().filter(|p| cost(**p).is_some()).count();
+            let allowed = match options.corner_cutting {
+                CornerCutting::Never => open == 2,
+                CornerCutting::IfOneOpen => open >= 1,
+            };
+
+            if allowed {
+                steps.push((target, tile_cost * DIAGONAL_COST));
+            }
+        } else {
+            steps.push((target, tile_cost * CARDINAL_COST));
+        }
+    }
+
+    steps.into_iter()
+}
+
+/// Estimate the cost between two tiles assuming every tile has a movement cost of 1
+fn octile_distance(a: [i32; 2], b: [i32; 2]) -> u32 {
+    let dx = a[0].abs_diff(b[0]);
+    let dy = a[1].abs_diff(b[1]);
+    CARDINAL_COST * dx.max(dy) + (DIAGONAL_COST - CARDINAL_COST) * dx.min(dy)
+}
+
+/// Find the cheapest path between two tiles with A*
+///
+/// The returned path excludes `start` and ends with `goal`. `cost` returns the movement cost
+/// of a tile, or `None` if it can't be entered.
+pub fn find_path<F>(start: [i32; 2], goal: [i32; 2], options: &PathOptions, cost: F) -> Option<Vec<[i32; 2]>>
+where
+    F: Fn([i32; 2]) -> Option<u32>,
+{
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<[i32; 2], [i32; 2]> = HashMap::new();
+    let mut costs = HashMap::from([(start, 0)]);
+    let mut expanded = 0;
+
+    open.push(Reverse((octile_distance(start, goal), start)));
+    while let Some(Reverse((estimate, current))) = open.pop() {
+        // Skip entries left behind after a cheaper way to the same tile was found
+        if costs[&current] + octile_distance(current, goal) < estimate {
+            continue;
+        }
+
+        if current == goal {
+            let mut path = vec![goal];
+            while let Some(previous) = came_from.get(path.last().unwrap()) {
+                path.push(*previous);
+            }
+
+            path.pop();
+            path.reverse();
+            return Some(path);
+        }
+
+        expanded += 1;
+        if expanded > options.search_limit {
+            return None;
+        }
+
+        let current_cost = costs[&current];
+        for (next, step_cost) in neighbours(current, options, &cost) {
+            let next_cost = current_cost + step_cost;
+            if costs.get(&next).is_none_or(|c| next_cost < *c) {
+                costs.insert(next, next_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((next_cost + octile_distance(next, goal), next)));
+            }
+        }
+    }
+
+    None
+}
+
+/// The distance from every reachable tile to the nearest of a set of goals
+#[derive(Clone, Debug, Default)]
+pub struct DijkstraMap {
+    distances: HashMap<[i32; 2], i32>,
+}
+
+impl DijkstraMap {
+    /// Build a map leading towards any of `goals`
+    pub fn new<F>(goals: &[[i32; 2]], options: &PathOptions, cost: F) -> Self
+    where
+        F: Fn([i32; 2]) -> Option<u32>,
+    {
+        Self::relax(goals.iter().map(|goal| (*goal, 0)).collect(), options, &cost)
+    }
+
+    /// Build a map leading away from this map's goals that prefers escape routes
+    /// over corners
+    pub fn flee<F>(&self, options: &PathOptions, cost: F) -> Self
+    where
+        F: Fn([i32; 2]) -> Option<u32>,
+    {
+        let inverted = self.distances.iter().map(|(p, d)| (*p, -d * 6 / 5)).collect();
+        Self::relax(inverted, options, &cost)
+    }
+
+    fn relax<F>(initial: HashMap<[i32; 2], i32>, options: &PathOptions, cost: &F) -> Self
+    where
+        F: Fn([i32; 2]) -> Option<u32>,
+    {
+        let mut open: BinaryHeap<_> = initial.iter().map(|(p, d)| Reverse((*d, *p))).collect();
+        let mut distances = initial;
+        let mut expanded = 0;
+
+        while let Some(Reverse((distance, current))) = open.pop() {
+            if distances[&current] < distance {
+                continue;
+            }
+
+            expanded += 1;
+            if expanded > options.search_limit {
+                break;
+            }
+
+            for (next, step_cost) in neighbours(current, options, cost) {
+                let next_distance = distance + step_cost as i32;
+                if distances.get(&next).is_none_or(|d| next_distance < *d) {
+                    distances.insert(next, next_distance);
+                    open.push(Reverse((next_distance, next)));
+                }
+            }
+        }
+
+        Self { distances }
+    }
+
+    pub fn distance(&self, position: [i32; 2]) -> Option<i32> {
+        self.distances.get(&position).copied()
+    }
+
+    /// Get the neighbouring tile that most reduces the distance, if any does
+    pub fn descend<F>(&self, position: [i32; 2], options: &PathOptions, cost: F) -> Option<[i32; 2]>
+    where
+        F: Fn([i32; 2]) -> Option<u32>,
+    {
+        let current = self.distance(position)?;
+        neighbours(position, options, &cost)
+            .filter_map(|(next, _)| Some((self.distance(next)?, next)))
+            .filter(|(distance, _)| *distance < current)
+            .min()
+            .map(|(_, next)| next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::CornerCutting;
+    use super::DijkstraMap;
+    use super::PathOptions;
+    use super::find_path;
+
+    fn cost<'a>(map: &'a [&str]) -> impl Fn([i32; 2]) -> Option<u32> + 'a {
+        move |[x, y]| match map.get(y as usize)?.as_bytes().get(x as usize)? {
+            b'.' => Some(1),
+            b'~' => Some(5),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn straight_and_diagonal() {
+        let map = ["....."; 5];
+        let path = find_path([0, 0], [4, 2], &PathOptions::default(), cost(&map)).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.last(), Some(&[4, 2]));
+    }
+
+    #[test]
+    fn avoids_expensive_tiles() {
+        let map = [
+            ".....",
+            ".~~~.",
+            ".....",
+        ];
+
+        let path = find_path([0, 1], [4, 1], &PathOptions::default(), cost(&map)).unwrap();
+        assert!(!path.contains(&[2, 1]));
+    }
+
+    #[test]
+    fn corner_cutting() {
+        let map = [
+            ".#",
+            "..",
+        ];
+
+        let never = PathOptions { corner_cutting: CornerCutting::Never, ..Default::default() };
+        assert_eq!(find_path([0, 0], [1, 1], &never, cost(&map)).unwrap().len(), 2);
+
+        let one_open = PathOptions { corner_cutting: CornerCutting::IfOneOpen, ..Default::default() };
+        assert_eq!(find_path([0, 0], [1, 1], &one_open, cost(&map)).unwrap(), vec![[1, 1]]);
+
+        let map = [
+            ".#",
+            "#.",
+        ];
+
+        assert!(find_path([0, 0], [1, 1], &one_open, cost(&map)).is_none());
+    }
+
+    #[test]
+    fn search_limit() {
+        let map = [".........."; 10];
+        let limited = PathOptions { search_limit: 3, ..Default::default() };
+        assert!(find_path([0, 0], [9, 9], &limited, cost(&map)).is_none());
+        assert!(find_path([0, 0], [9, 9], &PathOptions::default(), cost(&map)).is_some());
+    }
+
+    #[test]
+    fn crosses_chunks() {
+        let world = World {
+            width: 2,
+            chunks: vec![[[Tile::Planks as u8; 16]; 16]; 4],
+            ..Default::default()
+        };
+
+        let path = find_path([2, 2], [29, 30], &PathOptions::default(), |p| world.movement_cost(p)).unwrap();
+        assert_eq!(path.last(), Some(&[29, 30]));
+        assert!(find_path([2, 2], [40, 2], &PathOptions::default(), |p| world.movement_cost(p)).is_none());
+    }
+
+    #[test]
+    fn dijkstra_approach_and_flee() {
+        let map = [
+            "........",
+            "........",
+            "........",
+        ];
+
+        let options = PathOptions::default();
+        let approach = DijkstraMap::new(&[[0, 1], [7, 1]], &options, cost(&map));
+        assert_eq!(approach.distance([7, 1]), Some(0));
+        assert_eq!(approach.distance([2, 1]), Some(20));
+        assert_eq!(approach.descend([2, 1], &options, cost(&map)), Some([1, 1]));
+
+        let flee = DijkstraMap::new(&[[0, 1]], &options, cost(&map)).flee(&options, cost(&map));
+        let step = flee.descend([3, 1], &options, cost(&map)).unwrap();
+        assert_eq!(step[0], 4);
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat::DamageSource;
+use crate::ecs::EntityId;
+use crate::equipment::Modifiers;
+use crate::log::Event;
+use crate::world::World;
+
+/// The experience needed to go from level 1 to level 2, which grows with every level after
+pub const BASE_EXPERIENCE: u32 = 50;
+/// Newly explored tiles are worth a single experience point per this many
+pub const TILES_PER_EXPERIENCE: u32 = 10;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Perk {
+    Toughness,
+    Strength,
+    Agility,
+    Quickness,
+    KeenEyes,
+    ThickSkin,
+    Marksman,
+}
+
+/// What a perk grants and who may take it
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct PerkDefinition {
+    pub name: String,
+    /// The lowest level at which the perk may be taken
+    pub level: u32,
+    /// The number of times the perk may be taken
+    pub max_rank: u32,
+    /// Stat adjustments per rank
+    #[serde(default)]
+    pub modifiers: Modifiers,
+    /// Maximum health gained per rank
+    #[serde(default)]
+    pub health: i32,
+}
+
+/// What every perk grants, read from `data/perks.json` the first time one is needed
+static DEFINITIONS: OnceLock<BTreeMap<Perk, PerkDefinition>> = OnceLock::new();
+
+impl Perk {
+    pub const ALL: [Perk; 7] = [
+        Perk::Toughness,
+        Perk::Strength,
+        Perk::Agility,
+        Perk::Quickness,
+        Perk::KeenEyes,
+        Perk::ThickSkin,
+        Perk::Marksman,
+    ];
+
+    /// Get what a specific perk grants
+    pub fn definition(self) -> &'static PerkDefinition {
+        let definitions = DEFINITIONS.get_or_init(|| {
+            serde_json::from_str(include_str!("../data/perks.json")).expect("perk definitions should be valid")
+        });
+
+        &definitions[&self]
+    }
+}
+
+/// The player's experience, level and the perks they've taken
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Progression {
+    pub level: u32,
+    /// Experience gained since the last level
+    pub experience: u32,
+    /// Perks that may still be picked from levels gained
+    pub unspent: u32,
+    pub perks: Vec<Perk>,
+    /// Tiles explored so far, used to award experience for exploring
+    pub explored: u32,
+}
+
+impl Default for Progression {
+    fn default() -> Self {
+        Self { level: 1, experience: 0, unspent: 0, perks: vec![], explored: 0 }
+    }
+}
+
+impl Progression {
+    /// Get the experience needed to reach the next level
+    pub fn required(&self) -> u32 {
+        BASE_EXPERIENCE * self.level
+    }
+
+    /// Add experience, levelling up as many times as it allows
+    pub fn gain(&mut self, experience: u32) {
+        self.experience += experience;
+        while self.experience >= self.required() {
+            self.experience -= self.required();
+            self.level += 1;
+            self.unspent += 1;
+        }
+    }
+
+    pub fn rank(&self, perk: Perk) -> u32 {
+        self.perks.iter().filter(|p| **p == perk).count() as u32
+    }
+
+    /// Get the perks that could be picked right now
+    pub fn choices(&self) -> Vec<Perk> {
+        if self.unspent == 0 {
+            return vec![];
+        }
+
+        Perk::ALL
+            .iter()
+            .copied()
+            .filter(|perk| perk.definition().level <= self.level && self.rank(*perk) < perk.definition().max_rank)
+            .collect()
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.perks.iter().fold(Modifiers::default(), |total, perk| total + perk.definition().modifiers)
+    }
+}
+
+impl World {
+    /// Give the player experience
+    pub fn grant_experience(&mut self, experience: u32) {
+        let progression = match self.progressions.get_mut(&self.player) {
+            Some(progression) => progression,
+            None => return,
+        };
+
+        let level = progression.level;
+        progression.gain(experience);
+        self.notify_level_up(level);
+    }
+
+    /// Give the player the experience an entity is worth when it dies
+    ///
+    /// Monsters killing each other doesn't teach the player anything, but traps and fire they
+    /// lure something into still count.
+    pub fn reward_kill(&mut self, id: EntityId, source: DamageSource) {
+        if source.culprit().is_some_and(|culprit| culprit != self.player) {
+            return;
+        }
+
+        if let Some(reward) = self.rewards.get(&id).copied() {
+            self.grant_experience(reward);
+        }
+    }
+
+    /// Count newly explored tiles towards the player's experience
+    pub fn record_exploration(&mut self, tiles: u32) {
+        let progression = match self.progressions.get_mut(&self.player) {
+            Some(progression) => progression,
+            None => return,
+        };
+
+        let (level, before) = (progression.level, progression.explored / TILES_PER_EXPERIENCE);
+        progression.explored += tiles;
+        progression.gain(progression.explored / TILES_PER_EXPERIENCE - before);
+        self.notify_level_up(level);
+    }
+
+    /// Announce the player's new level if it has changed since `previous`
+    fn notify_level_up(&mut self, previous: u32) {
+        let level = self.progressions.get(&self.player).map_or(previous, |progression| progression.level);
+        if level > previous {
+            self.notify(Event::LevelledUp(level));
+        }
+    }
+
+    /// Spend an unspent level on a perk, returning whether it could be taken
+    pub fn pick_perk(&mut self, id: EntityId, perk: Perk) -> bool {
+        let progression = match self.progressions.get_mut(&id) {
+            Some(progression) => progression,
+            None => return false,
+        };
+
+        if !progression.choices().contains(&perk) {
+            return false;
+        }
+
+        progression.unspent -= 1;
+        progression.perks.push(perk);
+
+        let health = perk.definition().health;
+        if let Some(current) = self.healths.get_mut(&id) {
+            current.maximum += health;
+            current.current += health;
+        }
+
+        true
+    }
+
+    /// Describe the player's level and any perks waiting to be picked
+    pub fn progression_summary(&self) -> Option<String> {
+        let progression = self.progressions.get(&self.player)?;
+        let mut summary = format!(
+            "Level {} ({}/{} XP)",
+            progression.level,
+            progression.experience,
+            progression.required(),
+        );
+
+        let choices = progression.choices();
+        if !choices.is_empty() {
+            summary.push_str(" - Level up! Choose a perk:");
+            for (index, perk) in choices.iter().enumerate() {
+                summary.push_str(&format!(" F{} {}", index + 1, perk.definition().name));
+            }
+        }
+
+        Some(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::combat::DamageSource;
+    use crate::health::Health;
+    use crate::world::World;
+
+    use super::BASE_EXPERIENCE;
+    use super::Perk;
+    use super::Progression;
+    use super::TILES_PER_EXPERIENCE;
+
+    #[test]
+    fn levelling() {
+        let mut progression = Progression::default();
+        progression.gain(49);
+        assert_eq!(progression.level, 1);
+        progression.gain(1 + 100 + 10);
+        assert_eq!((progression.level, progression.experience, progression.unspent), (3, 10, 2));
+
+        assert!(progression.choices().contains(&Perk::KeenEyes));
+        assert!(!progression.choices().contains(&Perk::ThickSkin));
+    }
+
+    #[test]
+    fn perks_and_persistence() {
+        let mut world = World::default();
+        let player = world.player;
+        world.healths.insert(player, Health::new(20));
+        world.progressions.insert(player, Progression::default());
+
+        assert!(!world.pick_perk(player, Perk::Toughness));
+        world.record_exploration(BASE_EXPERIENCE * TILES_PER_EXPERIENCE);
+        assert_eq!(world.progressions[&player].level, 2);
+
+        assert!(world.pick_perk(player, Perk::Toughness));
+        assert!(!world.pick_perk(player, Perk::Strength));
+        assert_eq!(world.healths[&player], Health { current: 25, maximum: 25 });
+
+        world.grant_experience(100);
+        assert!(world.pick_perk(player, Perk::KeenEyes));
+        assert_eq!(world.sight_radius(player, 8), 10);
+        assert_eq!(world.progressions[&player].choices(), vec![]);
+
+        let saved = serde_json::to_string(&world).unwrap();
+        let loaded: World = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.progressions[&player], world.progressions[&player]);
+    }
+
+    #[test]
+    fn only_the_player_learns_from_kills() {
+        let mut world = World::default();
+        let player = world.player;
+        world.progressions.insert(player, Progression::default());
+
+        let victim = |world: &mut World| {
+            let id = world.spawn();
+            world.healths.insert(id, Health::new(1));
+            world.rewards.insert(id, 10);
+            id
+        };
+
+        let monster = victim(&mut world);
+        let prey = victim(&mut world);
+        world.damage(prey, 1, DamageSource::Entity(monster));
+        assert_eq!(world.progressions[&player].experience, 0);
+
+        let prey = victim(&mut world);
+        world.damage(prey, 1, DamageSource::Entity(player));
+        let prey = victim(&mut world);
+        world.damage(prey, 1, DamageSource::Explosion(None));
+        assert_eq!(world.progressions[&player].experience, 20);
+    }
+
+    #[test]
+    fn definitions_load() {
+        for perk in Perk::ALL {
+            assert!(!perk.definition().name.is_empty());
+        }
+    }
+}
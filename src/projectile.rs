@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat::Attack;
+use crate::combat::AttackOutcome;
+use crate::combat::resolve_attack;
+use crate::ecs::EntityId;
+use crate::entity::Entity;
+use crate::line::bresenham;
+use crate::log::Event;
+use crate::rng::Stream;
+use crate::world::World;
+
+/// The ability to launch projectiles, granted by bows, wands and throwable items
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Ranged {
+    pub attack: Attack,
+    /// The furthest a projectile travels, in tiles
+    pub range: i32,
+    /// Tiles travelled per tick
+    pub speed: u32,
+    pub atlas_position: [u32; 2],
+    pub color: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Projectile {
+    pub owner: EntityId,
+    pub attack: Attack,
+    pub position: [i32; 2],
+    /// The tiles still to be travelled through
+    pub path: VecDeque<[i32; 2]>,
+    pub speed: u32,
+}
+
+impl World {
+    /// Get the tiles a projectile would pass through when aimed from one tile at another
+    ///
+    /// The line carries on past `to` until it reaches `range` or is stopped by a tile that is
+    /// solid or opaque.
+    pub fn line_of_fire(&self, from: [i32; 2], to: [i32; 2], range: i32) -> Vec<[i32; 2]> {
+        let [dx, dy] = [to[0] - from[0], to[1] - from[1]];
+        let length = dx.abs().max(dy.abs());
+        if length == 0 {
+            return vec![];
+        }
+
+        let scale = range / length + 1;
+        bresenham(from, [from[0] + dx * scale, from[1] + dy * scale])
+            .into_iter()
+            .take(range.max(0) as usize)
+            .take_while(|p| self.tile(*p).is_some_and(|tile| !tile.is_solid() && !tile.is_opaque()))
+            .collect()
+    }
+
+    /// Launch a projectile from an entity towards a tile, returning whether one was fired
+    pub fn fire(&mut self, owner: EntityId, target: [i32; 2]) -> bool {
+        let (ranged, position) = match (self.ranged.get(&owner), self.positions.get(&owner)) {
+            (Some(ranged), Some(position)) => (*ranged, *position),
+            _ => return false,
+        };
+
+        let path: VecDeque<[i32; 2]> = self.line_of_fire(position, target, ranged.range).into();
+        if path.is_empty() {
+            return false;
+        }
+
+        let id = self.spawn();
+        let sprite_position = [position[0] as f32, position[1] as f32];
+        self.entities.insert(id, Entity::new(sprite_position, ranged.atlas_position, [1, 1], ranged.color, None));
+        self.projectiles.insert(id, Projectile {
+            owner,
+            attack: ranged.attack,
+            position,
+            path,
+            speed: ranged.speed,
+        });
+
+        true
+    }
+
+    /// Move every projectile along its path, striking the first entity in its way
+    pub fn update_projectiles(&mut self) {
+        let ids: Vec<EntityId> = self.projectiles.keys().copied().collect();
+        for id in ids {
+            let mut projectile = match self.projectiles.remove(&id) {
+                Some(projectile) => projectile,
+                None => continue,
+            };
+
+            let mut spent = false;
+            for _ in 0..projectile.speed {
+                let next = match projectile.path.pop_front() {
+                    Some(next) => next,
+                    None => {
+                        spent = true;
+                        break;
+                    }
+                };
+
+                if self.tile(next).is_none_or(|tile| tile.is_solid()) {
+                    spent = true;
+                    break;
+                }
+
+                projectile.position = next;
+                if let Some(target) = self.entity_at(next) {
+                    if target != projectile.owner && self.healths.contains_key(&target) {
+                        let defense = self.defense(target);
+                        let outcome = resolve_attack(self.rng(Stream::Combat), &projectile.attack, &defense);
+                        self.notify(Event::Attacked { attacker: projectile.owner, defender: target, outcome });
+                        match outcome {
+                            AttackOutcome::Hit(damage) | AttackOutcome::Critical(damage) => {
+                                self.strike(projectile.owner, target, &projectile.attack, damage);
+                                spent = true;
+                                break;
+                            }
+                            AttackOutcome::Miss => (),
+                        }
+                    }
+                }
+            }
+
+            if spent || projectile.path.is_empty() {
+                self.despawn(id);
+            } else {
+                if let Some(entity) = self.entities.get_mut(&id) {
+                    entity.set_position([projectile.position[0] as f32, projectile.position[1] as f32]);
+                }
+
+                self.projectiles.insert(id, projectile);
+            }
+        }
+    }
+
+    /// Find the closest hostile entity the player can see
+    pub fn nearest_visible_hostile(&self) -> Option<[i32; 2]> {
+        let origin = *self.positions.get(&self.player)?;
+        self.positions
+            .iter()
+            .filter(|(id, p)| self.is_hostile(self.player, **id) && self.visibility.is_visible(**p))
+            .map(|(_, p)| *p)
+            .min_by_key(|p| (p[0] - origin[0]).pow(2) + (p[1] - origin[1]).pow(2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::combat::Attack;
+    use crate::health::Health;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::Ranged;
+
+    fn world() -> World {
+        let mut chunk = [[Tile::Planks as u8; 16]; 16];
+        chunk[5][10] = Tile::Wall as u8;
+
+        let mut world = World::test(chunk, [0, 5]);
+        let archer = world.player;
+        world.ranged.insert(archer, Ranged {
+            attack: Attack { damage: 3, accuracy: 200, critical_chance: 0, effect: None },
+            range: 12,
+            speed: 2,
+            atlas_position: [3, 0],
+            color: u32::MAX,
+        });
+
+        world
+    }
+
+    #[test]
+    fn line_of_fire_stops_at_walls() {
+        let world = world();
+        assert_eq!(world.line_of_fire([0, 5], [2, 5], 12).len(), 9);
+        assert_eq!(world.line_of_fire([0, 5], [2, 5], 4).len(), 4);
+        assert_eq!(world.line_of_fire([0, 5], [0, 5], 4).len(), 0);
+    }
+
+    #[test]
+    fn projectile_hits_first_entity() {
+        let mut world = world();
+        let archer = world.player;
+        let near = world.spawn();
+        let far = world.spawn();
+        for (id, position) in [(near, [3, 5]), (far, [6, 5])] {
+            world.positions.insert(id, position);
+            world.healths.insert(id, Health::new(10));
+        }
+
+        assert!(world.fire(archer, [6, 5]));
+        assert_eq!(world.projectiles.len(), 1);
+
+        world.update_projectiles();
+        assert_eq!(world.healths[&near].current, 10);
+        world.update_projectiles();
+        assert_eq!(world.healths[&near].current, 7);
+        assert_eq!(world.healths[&far].current, 10);
+        assert!(world.projectiles.is_empty());
+        assert!(world.entities.is_empty());
+    }
+}
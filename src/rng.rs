@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::world::World;
+
+/// A small deterministic generator (SplitMix64) so that gameplay can be reproduced from a seed
+///
+/// Only integer arithmetic feeds the state, so the numbers are the same on every platform.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Derive a generator from a list of keys, so the same keys always produce the same numbers
+    pub fn from_keys(keys: &[u64]) -> Self {
+        let state = keys.iter().fold(0, |state, key| Rng::new(state ^ key).next_u64());
+        Self::new(state)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Get a number within a range, which must not be empty
+    pub fn range(&mut self, range: Range<i32>) -> i32 {
+        let span = range.end.abs_diff(range.start) as u64;
+        range.start + ((self.next_u32() as u64 * span) >> 32) as i32
+    }
+
+    /// Return true with the given probability
+    pub fn chance(&mut self, probability: f32) -> bool {
+        (self.next_u32() as f64) < probability as f64 * (u32::MAX as f64 + 1.0)
+    }
+}
+
+/// The independent sequences of random numbers drawn from by each part of the game
+///
+/// Keeping them apart means, for example, that an extra combat roll doesn't change what the
+/// map generator builds next.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Stream {
+    Mapgen,
+    Loot,
+    Ai,
+    Combat,
+    Traps,
+    /// What unidentified items look like
+    Appearances,
+}
+
+impl Stream {
+    /// Get the name a specific stream is derived from, which must never change
+    pub fn name(self) -> &'static str {
+        match self {
+            Stream::Mapgen => "mapgen",
+            Stream::Loot => "loot",
+            Stream::Ai => "ai",
+            Stream::Combat => "combat",
+            Stream::Traps => "traps",
+            Stream::Appearances => "appearances",
+        }
+    }
+
+    /// Hash the stream's name (FNV-1a) rather than relying on the order of the variants
+    fn key(self) -> u64 {
+        self.name()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+    }
+}
+
+/// One generator per stream, each created from the world seed the first time it's used
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Streams {
+    rngs: BTreeMap<Stream, Rng>,
+}
+
+impl Streams {
+    /// Get the generator for a stream
+    pub fn get(&mut self, seed: u32, stream: Stream) -> &mut Rng {
+        self.rngs.entry(stream).or_insert_with(|| Rng::from_keys(&[seed as u64, stream.key()]))
+    }
+
+    /// Split a new generator off a stream for a specific purpose, such as a position on the map
+    ///
+    /// This doesn't advance the stream, so the same keys always give the same generator no
+    /// matter what else has happened.
+    pub fn derive(seed: u32, stream: Stream, keys: &[u64]) -> Rng {
+        let mut rng = Rng::from_keys(&[seed as u64, stream.key()]);
+        for key in keys {
+            rng = Rng::from_keys(&[rng.next_u64(), *key]);
+        }
+
+        rng
+    }
+}
+
+impl World {
+    /// Get the generator for a stream of the world's seed
+    pub fn rng(&mut self, stream: Stream) -> &mut Rng {
+        self.streams.get(self.seed, stream)
+    }
+
+    /// Split a new generator off a stream of the world's seed, as with `Streams::derive`
+    pub fn derive_rng(&self, stream: Stream, keys: &[u64]) -> Rng {
+        Streams::derive(self.seed, stream, keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::World;
+
+    use super::Rng;
+    use super::Stream;
+    use super::Streams;
+
+    #[test]
+    fn stable_output() {
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(Streams::default().get(42, Stream::Combat).next_u64(), 0x0ecf_92e6_74c9_d6ee);
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        let mut a = Streams::default();
+        let mut b = Streams::default();
+        for _ in 0..10 {
+            a.get(1, Stream::Combat).next_u64();
+        }
+
+        assert_eq!(a.get(1, Stream::Ai).next_u64(), b.get(1, Stream::Ai).next_u64());
+        assert_ne!(a.get(1, Stream::Ai).next_u64(), b.get(1, Stream::Combat).next_u64());
+        assert_eq!(Streams::derive(1, Stream::Loot, &[3, 4]), Streams::derive(1, Stream::Loot, &[3, 4]));
+        assert_ne!(Streams::derive(1, Stream::Loot, &[3, 4]), Streams::derive(1, Stream::Loot, &[4, 3]));
+        assert_ne!(Streams::derive(2, Stream::Loot, &[3, 4]), Streams::derive(1, Stream::Loot, &[3, 4]));
+    }
+
+    #[test]
+    fn world_streams_follow_the_seed() {
+        let mut world = World { seed: 42, ..Default::default() };
+        assert_eq!(world.rng(Stream::Combat).next_u64(), 0x0ecf_92e6_74c9_d6ee);
+        assert_eq!(world.derive_rng(Stream::Loot, &[3, 4]), Streams::derive(42, Stream::Loot, &[3, 4]));
+    }
+
+    #[test]
+    fn range_and_chance() {
+        let mut rng = Rng::new(5);
+        for _ in 0..1000 {
+            assert!((-3..4).contains(&rng.range(-3..4)));
+        }
+
+        assert!((0..1000).all(|_| rng.chance(1.0)));
+        assert!((0..1000).all(|_| !rng.chance(0.0)));
+    }
+}
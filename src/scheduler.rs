@@ -0,0 +1,217 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::action::Action;
+use crate::action::ActionQueue;
+use crate::ecs::EntityId;
+use crate::time::DeltaTime;
+use crate::time::FixedTick;
+use crate::world::World;
+
+/// The energy an actor must have accumulated before it may act
+pub const ACTION_THRESHOLD: i32 = 100;
+/// The length of a real-time tick in seconds
+pub const TICK_PERIOD: f32 = 0.1;
+/// An upper bound on ticks simulated in a single turn, in case nobody can ever act
+const MAX_TICKS_PER_TURN: u32 = 1000;
+
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SimulationMode {
+    /// Time only passes when the player acts
+    #[default]
+    TurnBased,
+    /// Time passes on a fixed tick whether or not the player acts
+    RealTime,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Energy {
+    /// Energy gained per tick
+    pub speed: i32,
+    pub energy: i32,
+}
+
+impl Energy {
+    pub fn new(speed: i32) -> Self {
+        Self { speed, energy: 0 }
+    }
+}
+
+impl Default for Energy {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+pub struct Scheduler {
+    tick: FixedTick,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self { tick: FixedTick::new(TICK_PERIOD) }
+    }
+
+    /// Advance the simulation according to the world's simulation mode
+    ///
+    /// `decide` is asked for the next action of every actor other than the player,
+    /// whose actions are expected to already be in the queue.
+    pub fn update<F>(&mut self, world: &mut World, queue: &mut ActionQueue, dt: DeltaTime, mut decide: F)
+    where
+        F: FnMut(&mut World, EntityId) -> Action,
+    {
+        match world.mode {
+            SimulationMode::TurnBased => {
+                // Anything the player's action set flying lands before they're asked for the next one
+                let mut ticks = 0;
+                while (queue.is_pending(world.player) || !world.projectiles.is_empty()) && ticks < MAX_TICKS_PER_TURN {
+                    tick(world, queue, &mut decide);
+                    ticks += 1;
+                }
+            }
+            SimulationMode::RealTime => {
+                for _ in 0..self.tick.advance(dt) {
+                    tick(world, queue, &mut decide);
+                }
+            }
+        }
+    }
+}
+
+/// Grant every actor energy and let those that have enough perform an action
+fn tick<F>(world: &mut World, queue: &mut ActionQueue, decide: &mut F)
+where
+    F: FnMut(&mut World, EntityId) -> Action,
+{
+    world.update_projectiles();
+    world.update_statuses(DeltaTime(TICK_PERIOD));
+    world.update_abilities(DeltaTime(TICK_PERIOD));
+    world.update_fuses(DeltaTime(TICK_PERIOD));
+
+    let mut ready = vec![];
+    let ids: Vec<EntityId> = world.energies.keys().copied().collect();
+    for id in ids {
+        let speed = world.speed(id);
+        let energy = world.energies.get_mut(&id).unwrap();
+
+        // Actors waiting on input shouldn't bank energy while they wait
+        energy.energy = (energy.energy + speed).min(ACTION_THRESHOLD + speed);
+        if energy.energy >= ACTION_THRESHOLD {
+            ready.push(id);
+        }
+    }
+
+    for id in ready {
+        if id != world.player && !queue.is_pending(id) {
+            let action = decide(world, id);
+            queue.push(id, action);
+        }
+
+        let action = match queue.take(id) {
+            Some(action) => action,
+            None => continue,
+        };
+
+        if action.perform(world, id) {
+            if let Some(energy) = world.energies.get_mut(&id) {
+                energy.energy -= action.cost();
+            }
+
+            // Monsters only listen out for the player
+            if id == world.player {
+                if let Some(position) = world.positions.get(&id).copied() {
+                    world.make_noise(position, action.noise());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::action::Action;
+    use crate::action::ActionQueue;
+    use crate::combat::Attack;
+    use crate::ecs::EntityId;
+    use crate::health::Health;
+    use crate::projectile::Ranged;
+    use crate::time::DeltaTime;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::Energy;
+    use super::Scheduler;
+    use super::SimulationMode;
+
+    fn world(mode: SimulationMode) -> World {
+        let mut world = World { mode, ..World::test([[Tile::Planks as u8; 16]; 16], [0, 0]) };
+
+        for (id, speed) in [(0, 10), (1, 10), (2, 20)] {
+            world.positions.insert(EntityId(id), [id as i32, 0]);
+            world.energies.insert(EntityId(id), Energy::new(speed));
+        }
+
+        world
+    }
+
+    fn count_actions(world: &mut World, queue: &mut ActionQueue, dt: f32) -> Vec<EntityId> {
+        let mut acted = vec![];
+        Scheduler::new().update(world, queue, DeltaTime(dt), |_, id| {
+            acted.push(id);
+            Action::Wait
+        });
+        acted
+    }
+
+    #[test]
+    fn turn_based_waits_for_player() {
+        let mut world = world(SimulationMode::TurnBased);
+        let mut queue = ActionQueue::new();
+
+        assert!(count_actions(&mut world, &mut queue, 10.0).is_empty());
+
+        queue.push(EntityId(0), Action::Move([0, 1]));
+        let acted = count_actions(&mut world, &mut queue, 0.0);
+        assert_eq!(acted.iter().filter(|id| **id == EntityId(1)).count(), 1);
+        assert_eq!(acted.iter().filter(|id| **id == EntityId(2)).count(), 2);
+        assert_eq!(world.positions[&EntityId(0)], [0, 1]);
+    }
+
+    #[test]
+    fn turn_based_lands_projectiles() {
+        let mut world = world(SimulationMode::TurnBased);
+        let mut queue = ActionQueue::new();
+        let target = world.spawn();
+        world.positions.insert(target, [8, 0]);
+        world.healths.insert(target, Health::new(10));
+        world.ranged.insert(EntityId(0), Ranged {
+            attack: Attack { damage: 3, accuracy: 200, critical_chance: 0, effect: None },
+            range: 12,
+            speed: 1,
+            atlas_position: [3, 0],
+            color: u32::MAX,
+        });
+
+        queue.push(EntityId(0), Action::Fire([8, 0]));
+        count_actions(&mut world, &mut queue, 0.0);
+        assert!(world.projectiles.is_empty());
+        assert_eq!(world.healths[&target].current, 7);
+    }
+
+    #[test]
+    fn real_time_acts_on_tick() {
+        let mut world = world(SimulationMode::RealTime);
+        let mut queue = ActionQueue::new();
+
+        assert!(count_actions(&mut world, &mut queue, 0.05).is_empty());
+
+        let acted = count_actions(&mut world, &mut queue, 1.0);
+        assert_eq!(acted, vec![EntityId(2), EntityId(1), EntityId(2)]);
+    }
+}
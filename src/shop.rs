@@ -0,0 +1,346 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ecs::EntityId;
+use crate::entity::Entity;
+use crate::faction::Faction;
+use crate::item::Item;
+use crate::item::ItemKind;
+use crate::log::Event;
+use crate::log::GREY;
+use crate::log::WHITE;
+use crate::log::YELLOW;
+use crate::loot::LootTable;
+use crate::rng::Stream;
+use crate::world::World;
+
+/// What shops charge on top of an item's value, in percent
+const BUY_MARKUP: i32 = 150;
+/// What shops pay for an item, as a percentage of its value
+const SELL_RATE: i32 = 50;
+/// The gold a shop starts with to buy from the player, plus this again for every level of depth
+const SHOP_GOLD: u32 = 100;
+
+/// A shopkeeper's wares, kept with the level so they're still there on a return visit
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Shop {
+    pub stock: Vec<Item>,
+    /// The gold the shopkeeper has left to buy things from the player with
+    pub gold: u32,
+}
+
+impl Shop {
+    /// Add items to the stock, merging them into matching stacks
+    fn restock(&mut self, item: Item) {
+        match self.stock.iter_mut().find(|stack| stack.kind == item.kind && stack.affixes == item.affixes) {
+            Some(stack) => stack.count += item.count,
+            None => self.stock.push(item),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum TradeMode {
+    Buy,
+    Sell,
+}
+
+/// A trade the player has open with a shopkeeper, and the item they have highlighted
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Trade {
+    pub shopkeeper: EntityId,
+    pub mode: TradeMode,
+    pub selected: usize,
+}
+
+impl World {
+    /// Place a shopkeeper whose stock is rolled from the seed, the depth and where they stand
+    pub fn spawn_shopkeeper(&mut self, position: [i32; 2]) -> EntityId {
+        let keys = [self.depth as u64, position[0] as u64, position[1] as u64];
+        let mut rng = self.derive_rng(Stream::Loot, &keys);
+
+        let mut shop = Shop { stock: vec![], gold: SHOP_GOLD * (self.depth + 1) };
+        for item in LootTable::Shop.generate(&mut rng, self.depth) {
+            shop.restock(item);
+        }
+
+        let id = self.spawn();
+        self.names.insert(id, "shopkeeper".to_string());
+        self.entities.insert(id, Entity::new([0.0, 0.0], [0, 0], [1, 1], 0xff40_d0ff, None));
+        self.factions.insert(id, Faction::Townsfolk);
+        self.shops.insert(id, shop);
+        self.move_entity(id, position);
+
+        id
+    }
+
+    /// Get the percentage a shopkeeper's prices are shifted by, as a better standing earns better deals
+    fn haggle(&self, shopkeeper: EntityId) -> i32 {
+        match self.faction(shopkeeper) {
+            Some(faction) => self.relation(Faction::Player, faction) / 5,
+            None => 0,
+        }
+    }
+
+    /// Get what a shopkeeper charges for a single item
+    pub fn buy_price(&self, shopkeeper: EntityId, item: &Item) -> u32 {
+        let rate = (BUY_MARKUP - self.haggle(shopkeeper)).max(100);
+        (item.value() as i32 * rate / 100).max(1) as u32
+    }
+
+    /// Get what a shopkeeper pays for a single item, which is never more than they'd sell it for
+    pub fn sell_price(&self, shopkeeper: EntityId, item: &Item) -> u32 {
+        let rate = (SELL_RATE + self.haggle(shopkeeper) / 2).clamp(0, 100);
+        (item.value() as i32 * rate / 100).max(0) as u32
+    }
+
+    /// Start trading with an entity, returning whether it had a shop
+    pub fn open_shop(&mut self, shopkeeper: EntityId) -> bool {
+        if !self.shops.contains_key(&shopkeeper) || self.is_hostile(shopkeeper, self.player) {
+            return false;
+        }
+
+        self.trade = Some(Trade { shopkeeper, mode: TradeMode::Buy, selected: 0 });
+        true
+    }
+
+    /// Get the player's gold
+    pub fn gold(&self) -> u32 {
+        self.inventories.get(&self.player).map(|inventory| inventory.count(ItemKind::Gold)).unwrap_or_default()
+    }
+
+    /// Buy a single item from a slot of a shop's stock, returning whether the player could afford it
+    pub fn buy(&mut self, shopkeeper: EntityId, slot: usize) -> bool {
+        let item = match self.shops.get(&shopkeeper).and_then(|shop| shop.stock.get(slot)) {
+            Some(item) => Item { count: 1, ..item.clone() },
+            None => return false,
+        };
+
+        let price = self.buy_price(shopkeeper, &item);
+        let inventory = match self.inventories.get_mut(&self.player) {
+            Some(inventory) => inventory,
+            None => return false,
+        };
+
+        if inventory.count(ItemKind::Gold) < price || inventory.add(item.clone()).is_err() {
+            return false;
+        }
+
+        inventory.remove(ItemKind::Gold, price);
+        if let Some(shop) = self.shops.get_mut(&shopkeeper) {
+            shop.gold += price;
+            shop.stock[slot].count -= 1;
+            if shop.stock[slot].count == 0 {
+                shop.stock.remove(slot);
+            }
+        }
+
+        self.notify(Event::Bought(item.kind, price));
+        true
+    }
+
+    /// Sell a single item from a slot of the player's inventory, returning whether the shop took it
+    pub fn sell(&mut self, shopkeeper: EntityId, slot: usize) -> bool {
+        let item = match self.inventories.get(&self.player).and_then(|inventory| inventory.items.get(slot)) {
+            Some(item) if item.kind != ItemKind::Gold => Item { count: 1, ..item.clone() },
+            _ => return false,
+        };
+
+        let price = self.sell_price(shopkeeper, &item);
+        match self.shops.get_mut(&shopkeeper) {
+            Some(shop) if shop.gold >= price => {
+                shop.gold -= price;
+                shop.restock(item.clone());
+            }
+            _ => return false,
+        }
+
+        if let Some(inventory) = self.inventories.get_mut(&self.player) {
+            inventory.take(slot, 1);
+        }
+
+        if price > 0 {
+            self.give_player(Item::new(ItemKind::Gold, price));
+        }
+
+        self.notify(Event::Sold(item.kind, price));
+        true
+    }
+
+    /// Get the items on offer in the open trade, each with the slot it's in and its price
+    ///
+    /// Gold is what's paid, so it's never on offer itself.
+    pub fn trade_offers(&self) -> Vec<(usize, Item, u32)> {
+        let trade = match &self.trade {
+            Some(trade) => trade,
+            None => return vec![],
+        };
+
+        let items = match trade.mode {
+            TradeMode::Buy => self.shops.get(&trade.shopkeeper).map(|shop| &shop.stock),
+            TradeMode::Sell => self.inventories.get(&self.player).map(|inventory| &inventory.items),
+        };
+
+        items
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, item)| item.kind != ItemKind::Gold)
+            .map(|(slot, item)| match trade.mode {
+                TradeMode::Buy => (slot, item.clone(), self.buy_price(trade.shopkeeper, item)),
+                TradeMode::Sell => (slot, item.clone(), self.sell_price(trade.shopkeeper, item)),
+            })
+            .collect()
+    }
+
+    /// Move the highlighted item up or down, wrapping around
+    pub fn select_offer(&mut self, offset: i32) {
+        let count = self.trade_offers().len() as i32;
+        if let Some(trade) = self.trade.as_mut() {
+            if count > 0 {
+                trade.selected = (trade.selected as i32 + offset).rem_euclid(count) as usize;
+            }
+        }
+    }
+
+    /// Swap between buying and selling
+    pub fn switch_trade_mode(&mut self) {
+        if let Some(trade) = self.trade.as_mut() {
+            trade.mode = match trade.mode {
+                TradeMode::Buy => TradeMode::Sell,
+                TradeMode::Sell => TradeMode::Buy,
+            };
+            trade.selected = 0;
+        }
+    }
+
+    /// Buy or sell the highlighted item
+    pub fn confirm_offer(&mut self) {
+        let (trade, slot) = match (self.trade, self.trade_offers()) {
+            (Some(trade), offers) => match offers.get(trade.selected) {
+                Some((slot, _, _)) => (trade, *slot),
+                None => return,
+            },
+            _ => return,
+        };
+
+        match trade.mode {
+            TradeMode::Buy => self.buy(trade.shopkeeper, slot),
+            TradeMode::Sell => self.sell(trade.shopkeeper, slot),
+        };
+
+        // Keep the highlight on the list if the last of something just changed hands
+        self.select_offer(0);
+    }
+
+    /// Get the lines of the trade panel, or `None` if the player isn't trading
+    ///
+    /// The list scrolls so the highlighted item is always among the `rows` lines.
+    pub fn trade_lines(&self, rows: usize) -> Option<Vec<(String, u32)>> {
+        let trade = self.trade.as_ref()?;
+        let verb = match trade.mode {
+            TradeMode::Buy => "Buying from",
+            TradeMode::Sell => "Selling to",
+        };
+
+        let shop_gold = self.shops.get(&trade.shopkeeper).map(|shop| shop.gold).unwrap_or_default();
+        let header = format!("{} {} ({} gold). You have {} gold", verb, self.name_of(trade.shopkeeper), shop_gold, self.gold());
+        let mut lines = vec![(header, WHITE)];
+
+        let visible = rows.saturating_sub(1).max(1);
+        let first = (trade.selected + 1).saturating_sub(visible);
+        for (index, (_, item, price)) in self.trade_offers().iter().enumerate().skip(first).take(visible) {
+            let text = format!("{} x{} - {} gold", self.item_name(item.kind), item.count, price);
+            match index == trade.selected {
+                true => lines.push((format!("> {text}"), YELLOW)),
+                false => lines.push((format!("  {text}"), GREY)),
+            }
+        }
+
+        Some(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::faction::Faction;
+    use crate::inventory::Inventory;
+    use crate::item::Item;
+    use crate::item::ItemKind;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    #[test]
+    fn stock_is_seeded() {
+        let stock = |seed| {
+            let mut world = World { seed, depth: 2, ..World::test([[Tile::Planks as u8; 16]; 16], [0, 0]) };
+            let shopkeeper = world.spawn_shopkeeper([4, 4]);
+            world.shops[&shopkeeper].stock.clone()
+        };
+
+        assert!(!stock(1).is_empty());
+        assert_eq!(stock(1), stock(1));
+        assert!((2..10).any(|seed| stock(seed) != stock(1)));
+    }
+
+    #[test]
+    fn buying_and_selling() {
+        let mut world = World { seed: 1, depth: 2, ..World::test([[Tile::Planks as u8; 16]; 16], [0, 0]) };
+        let player = world.player;
+        world.inventories.insert(player, Inventory::new(8, 100));
+        let shopkeeper = world.spawn_shopkeeper([4, 4]);
+        world.shops.get_mut(&shopkeeper).unwrap().stock = vec![Item::new(ItemKind::HealthPotion, 2)];
+
+        let potion = Item::new(ItemKind::HealthPotion, 1);
+        let (buy, sell) = (world.buy_price(shopkeeper, &potion), world.sell_price(shopkeeper, &potion));
+        assert!(buy > potion.value() && sell < potion.value());
+
+        assert!(!world.buy(shopkeeper, 0));
+        world.inventories.get_mut(&player).unwrap().add(Item::new(ItemKind::Gold, 100)).unwrap();
+        assert!(world.buy(shopkeeper, 0));
+        assert_eq!(world.gold(), 100 - buy);
+        assert_eq!(world.shops[&shopkeeper].stock[0].count, 1);
+
+        let slot = world.inventories[&player].items.iter().position(|item| item.kind == ItemKind::HealthPotion).unwrap();
+        assert!(world.sell(shopkeeper, slot));
+        assert_eq!(world.gold(), 100 - buy + sell);
+        assert_eq!(world.shops[&shopkeeper].stock[0].count, 2);
+        assert_eq!(world.inventories[&player].count(ItemKind::HealthPotion), 0);
+
+        let saved = serde_json::to_string(&world).unwrap();
+        let loaded: World = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.shops, world.shops);
+    }
+
+    #[test]
+    fn gold_that_doesnt_fit_is_dropped() {
+        let mut world = World { seed: 1, depth: 2, ..World::test([[Tile::Planks as u8; 16]; 16], [0, 0]) };
+        let player = world.player;
+        let shopkeeper = world.spawn_shopkeeper([4, 4]);
+        let mut inventory = Inventory::new(1, 100);
+        inventory.add(Item::new(ItemKind::HealthPotion, 2)).unwrap();
+        world.inventories.insert(player, inventory);
+
+        let price = world.sell_price(shopkeeper, &Item::new(ItemKind::HealthPotion, 1));
+        assert!(world.sell(shopkeeper, 0));
+        assert_eq!(world.gold(), 0);
+
+        let dropped = world.items_at([0, 0]);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(world.items[&dropped[0]], Item::new(ItemKind::Gold, price));
+    }
+
+    #[test]
+    fn reputation_affects_prices() {
+        let mut world = World { seed: 1, depth: 2, ..World::test([[Tile::Planks as u8; 16]; 16], [0, 0]) };
+        let shopkeeper = world.spawn_shopkeeper([4, 4]);
+        let dagger = Item::new(ItemKind::Dagger, 1);
+        let price = world.buy_price(shopkeeper, &dagger);
+
+        world.change_reputation(Faction::Townsfolk, -40);
+        assert!(world.buy_price(shopkeeper, &dagger) > price);
+
+        world.change_reputation(Faction::Townsfolk, -40);
+        assert!(!world.open_shop(shopkeeper));
+    }
+}
@@ -0,0 +1,242 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ai::Ai;
+use crate::combat::Attack;
+use crate::combat::Corpse;
+use crate::combat::Defense;
+use crate::ecs::EntityId;
+use crate::entity::Entity;
+use crate::faction::Faction;
+use crate::health::Health;
+use crate::level::Layout;
+use crate::loot::Loot;
+use crate::loot::LootTable;
+use crate::rng::Rng;
+use crate::rng::Stream;
+use crate::scheduler::Energy;
+use crate::status::StatusKind;
+use crate::world::World;
+
+/// Nothing is spawned closer than this many tiles to where the player enters a level
+pub const MIN_SPAWN_DISTANCE: i32 = 8;
+const MAX_ROOM_MONSTERS: i32 = 4;
+const MAX_ROOM_ITEMS: i32 = 2;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum MonsterKind {
+    Rat,
+    Goblin,
+    Orc,
+}
+
+pub struct MonsterDefinition {
+    pub name: &'static str,
+    pub atlas_position: [u32; 2],
+    pub color: u32,
+    pub health: i32,
+    pub attack: Attack,
+    pub defense: Defense,
+    pub faction: Faction,
+    pub loot: LootTable,
+    /// The experience granted for killing it
+    pub reward: u32,
+    pub speed: i32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpawnEntry {
+    pub kind: MonsterKind,
+    pub weight: u32,
+    /// The shallowest depth at which the entry can spawn
+    pub min_depth: u32,
+}
+
+const MONSTERS: &[SpawnEntry] = &[
+    SpawnEntry { kind: MonsterKind::Rat, weight: 6, min_depth: 0 },
+    SpawnEntry { kind: MonsterKind::Goblin, weight: 4, min_depth: 1 },
+    SpawnEntry { kind: MonsterKind::Orc, weight: 2, min_depth: 3 },
+];
+
+impl MonsterKind {
+    pub fn definition(self) -> MonsterDefinition {
+        match self {
+            MonsterKind::Rat => MonsterDefinition {
+                name: "rat",
+                atlas_position: [1, 0],
+                color: 0xff40_6080,
+                health: 4,
+                attack: Attack { damage: 1, ..Default::default() },
+                defense: Defense { armor: 0, evasion: 20 },
+                faction: Faction::Beasts,
+                loot: LootTable::Beast,
+                reward: 5,
+                speed: 12,
+            },
+            MonsterKind::Goblin => MonsterDefinition {
+                name: "goblin",
+                atlas_position: [1, 0],
+                color: u32::MAX,
+                health: 10,
+                attack: Attack { effect: Some(StatusKind::Poison), ..Default::default() },
+                defense: Defense::default(),
+                faction: Faction::Goblins,
+                loot: LootTable::Humanoid,
+                reward: 20,
+                speed: 10,
+            },
+            MonsterKind::Orc => MonsterDefinition {
+                name: "orc",
+                atlas_position: [1, 0],
+                color: 0xff40_a040,
+                health: 18,
+                attack: Attack { damage: 4, ..Default::default() },
+                defense: Defense { armor: 1, evasion: 0 },
+                faction: Faction::Orcs,
+                loot: LootTable::Humanoid,
+                reward: 40,
+                speed: 8,
+            },
+        }
+    }
+
+    /// Pick a monster that can appear at a specific depth, weighted by how common it is
+    pub fn roll(rng: &mut Rng, depth: u32) -> Option<MonsterKind> {
+        let entries: Vec<&SpawnEntry> = MONSTERS.iter().filter(|entry| entry.min_depth <= depth).collect();
+        let total: u32 = entries.iter().map(|entry| entry.weight).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = rng.range(0..total as i32) as u32;
+        for entry in entries {
+            if roll < entry.weight {
+                return Some(entry.kind);
+            }
+
+            roll -= entry.weight;
+        }
+
+        None
+    }
+}
+
+impl World {
+    /// Create a monster with everything it needs to act and fight
+    pub fn spawn_monster(&mut self, kind: MonsterKind, position: [i32; 2]) -> EntityId {
+        let definition = kind.definition();
+        let id = self.spawn();
+        self.names.insert(id, definition.name.to_string());
+        self.entities.insert(id, Entity::new([0.0, 0.0], definition.atlas_position, [1, 1], definition.color, None));
+        self.energies.insert(id, Energy::new(definition.speed));
+        self.healths.insert(id, Health::new(definition.health));
+        self.attacks.insert(id, definition.attack);
+        self.defenses.insert(id, definition.defense);
+        self.corpses.insert(id, Corpse { atlas_position: [2, 0], color: 0x80808080 });
+        self.factions.insert(id, definition.faction);
+        self.loot.insert(id, Loot { table: definition.loot, origin: position, kind });
+        self.rewards.insert(id, definition.reward);
+        self.ais.insert(id, Ai::new(position));
+        self.move_entity(id, position);
+
+        id
+    }
+
+    /// Fill the rooms of a freshly generated level with monsters and items
+    ///
+    /// Deeper levels get more and tougher monsters. Nothing is placed in reserved areas or near
+    /// the start, and each room only draws from its own generator derived from the seed, so the
+    /// same seed always populates a level the same way.
+    pub fn populate(&mut self, layout: &Layout) {
+        let start = layout.start;
+        for (index, room) in layout.rooms.iter().enumerate() {
+            let mut rng = self.derive_rng(Stream::Mapgen, &[self.depth as u64, index as u64]);
+            let mut free: Vec<[i32; 2]> = room
+                .tiles()
+                .filter(|p| (p[0] - start[0]).abs().max((p[1] - start[1]).abs()) >= MIN_SPAWN_DISTANCE)
+                .filter(|p| !layout.is_reserved(*p))
+                .filter(|p| self.is_passable(*p) && self.trap_at(*p).is_none() && self.items_at(*p).is_empty())
+                .collect();
+
+            let monsters = rng.range(0..(1 + self.depth as i32 / 2).min(MAX_ROOM_MONSTERS) + 1);
+            for _ in 0..monsters {
+                if free.is_empty() {
+                    break;
+                }
+
+                let position = free.swap_remove(rng.range(0..free.len() as i32) as usize);
+                if let Some(kind) = MonsterKind::roll(&mut rng, self.depth) {
+                    self.spawn_monster(kind, position);
+                }
+            }
+
+            let items = rng.range(0..MAX_ROOM_ITEMS + 1);
+            for _ in 0..items {
+                if free.is_empty() {
+                    break;
+                }
+
+                let position = free.swap_remove(rng.range(0..free.len() as i32) as usize);
+                for item in LootTable::Floor.generate(&mut rng, self.depth) {
+                    self.spawn_item(item, position);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::level::Layout;
+    use crate::level::Rect;
+    use crate::rng::Rng;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::MonsterKind;
+    use super::MIN_SPAWN_DISTANCE;
+
+    fn layout() -> Layout {
+        Layout {
+            rooms: vec![Rect::new([0, 0], [16, 8]), Rect::new([0, 8], [16, 8])],
+            reserved: vec![Rect::new([8, 8], [8, 8])],
+            start: [0, 0],
+        }
+    }
+
+    fn populated(seed: u32, depth: u32) -> Vec<[i32; 2]> {
+        let mut world = World { seed, depth, ..World::test([[Tile::Planks as u8; 16]; 16], [0, 0]) };
+        world.populate(&layout());
+        world.positions.iter().filter(|(id, _)| **id != world.player).map(|(_, p)| *p).collect()
+    }
+
+    #[test]
+    fn population_is_seeded() {
+        let placed = populated(3, 6);
+        assert!(!placed.is_empty());
+        assert_eq!(populated(3, 6), placed);
+        assert_ne!(populated(4, 6), placed);
+    }
+
+    #[test]
+    fn population_avoids_start_and_reserved() {
+        for seed in 0..20 {
+            for position in populated(seed, 6) {
+                assert!(position[0].max(position[1]) >= MIN_SPAWN_DISTANCE);
+                assert!(!layout().is_reserved(position));
+            }
+        }
+    }
+
+    #[test]
+    fn spawns_scale_with_depth() {
+        let mut rng = Rng::from_keys(&[1]);
+        assert!((0..100).all(|_| MonsterKind::roll(&mut rng, 0) == Some(MonsterKind::Rat)));
+        assert!((0..100).any(|_| MonsterKind::roll(&mut rng, 3) == Some(MonsterKind::Orc)));
+
+        let mut world = World { seed: 1, ..World::test([[Tile::Planks as u8; 16]; 16], [0, 0]) };
+        let goblin = world.spawn_monster(MonsterKind::Goblin, [4, 4]);
+        assert_eq!(world.name_of(goblin), "the goblin");
+        assert_eq!(world.entity_at([4, 4]), Some(goblin));
+    }
+}
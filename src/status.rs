@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat::DamageSource;
+use crate::ecs::EntityId;
+use crate::equipment::Modifiers;
+use crate::log::Event;
+use crate::time::DeltaTime;
+use crate::world::World;
+
+/// How long periodic effects such as poison wait between ticks, in seconds
+///
+/// In turn-based mode a second is the length of a standard turn.
+pub const STATUS_PERIOD: f32 = 1.0;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum StatusKind {
+    Poison,
+    Burning,
+    Slow,
+    Haste,
+    Blind,
+}
+
+/// What happens when a status is applied to an entity that already has it
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Stacking {
+    /// The remaining duration is reset to whichever is longer
+    Refresh,
+    /// The new duration is added onto what remains
+    Extend,
+    /// Another stack is added, up to `max_stacks`, and the duration refreshed
+    Intensify,
+}
+
+/// The rules a status follows, shared by every entity it's applied to
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct StatusDefinition {
+    /// How an afflicted entity is described, as in "you are poisoned"
+    pub adjective: String,
+    pub stacking: Stacking,
+    pub max_stacks: u32,
+    /// How long the status lasts when applied, in seconds
+    pub duration: f32,
+    /// Damage dealt per stack every `STATUS_PERIOD`
+    #[serde(default)]
+    pub damage: i32,
+    /// Stat adjustments per stack
+    #[serde(default)]
+    pub modifiers: Modifiers,
+}
+
+/// The rules for every status kind, read from `data/statuses.json` the first time one is needed
+static DEFINITIONS: OnceLock<BTreeMap<StatusKind, StatusDefinition>> = OnceLock::new();
+
+impl StatusKind {
+    /// Get the rules for a specific status kind
+    pub fn definition(self) -> &'static StatusDefinition {
+        let definitions = DEFINITIONS.get_or_init(|| {
+            serde_json::from_str(include_str!("../data/statuses.json")).expect("status definitions should be valid")
+        });
+
+        &definitions[&self]
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StatusEffect {
+    /// Seconds until the status wears off
+    pub remaining: f32,
+    pub stacks: u32,
+    /// Seconds since the status last dealt damage
+    pub elapsed: f32,
+}
+
+/// The statuses affecting an entity, at most one of each kind
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Statuses {
+    pub effects: BTreeMap<StatusKind, StatusEffect>,
+}
+
+impl Statuses {
+    /// Apply a status for a duration according to its stacking rules
+    pub fn apply(&mut self, kind: StatusKind, duration: f32) {
+        let definition = kind.definition();
+        let effect = match self.effects.get_mut(&kind) {
+            Some(effect) => effect,
+            None => {
+                self.effects.insert(kind, StatusEffect { remaining: duration, stacks: 1, elapsed: 0.0 });
+                return;
+            }
+        };
+
+        match definition.stacking {
+            Stacking::Refresh => effect.remaining = effect.remaining.max(duration),
+            Stacking::Extend => effect.remaining += duration,
+            Stacking::Intensify => {
+                effect.stacks = (effect.stacks + 1).min(definition.max_stacks);
+                effect.remaining = effect.remaining.max(duration);
+            }
+        }
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.effects
+            .iter()
+            .fold(Modifiers::default(), |total, (kind, effect)| total + kind.definition().modifiers * effect.stacks as i32)
+    }
+
+    /// Advance every status by `dt`, returning the damage each dealt and dropping those that wear off
+    pub fn update(&mut self, dt: DeltaTime) -> Vec<(StatusKind, i32)> {
+        let mut damage = vec![];
+        for (kind, effect) in self.effects.iter_mut() {
+            let elapsed = dt.0.min(effect.remaining);
+            effect.remaining -= dt.0;
+            effect.elapsed += elapsed;
+
+            let mut dealt = 0;
+            while effect.elapsed >= STATUS_PERIOD {
+                effect.elapsed -= STATUS_PERIOD;
+                dealt += kind.definition().damage * effect.stacks as i32;
+            }
+
+            if dealt > 0 {
+                damage.push((*kind, dealt));
+            }
+        }
+
+        self.effects.retain(|_, effect| effect.remaining > 0.0);
+        damage
+    }
+}
+
+impl World {
+    /// Afflict an entity with a status for its usual duration
+    pub fn apply_status(&mut self, id: EntityId, kind: StatusKind) {
+        if !self.healths.contains_key(&id) {
+            return;
+        }
+
+        self.statuses.entry(id).or_default().apply(kind, kind.definition().duration);
+        self.notify(Event::StatusApplied(id, kind));
+    }
+
+    /// Advance every entity's statuses, dealing any damage they cause
+    pub fn update_statuses(&mut self, dt: DeltaTime) {
+        let mut damaged = vec![];
+        for (id, statuses) in self.statuses.iter_mut() {
+            for (kind, damage) in statuses.update(dt) {
+                damaged.push((*id, kind, damage));
+            }
+        }
+
+        self.statuses.retain(|_, statuses| !statuses.effects.is_empty());
+        for (id, kind, damage) in damaged {
+            self.damage(id, damage, DamageSource::Status(kind));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::health::Health;
+    use crate::scheduler::Energy;
+    use crate::time::DeltaTime;
+    use crate::world::World;
+
+    use super::Stacking;
+    use super::StatusKind;
+    use super::Statuses;
+
+    #[test]
+    fn definitions_load() {
+        for kind in [StatusKind::Poison, StatusKind::Burning, StatusKind::Slow, StatusKind::Haste, StatusKind::Blind] {
+            assert!(!kind.definition().adjective.is_empty());
+        }
+
+        assert_eq!(StatusKind::Poison.definition().stacking, Stacking::Intensify);
+        assert_eq!(StatusKind::Blind.definition().modifiers.sight, -6);
+    }
+
+    #[test]
+    fn stacking_rules() {
+        let mut statuses = Statuses::default();
+        statuses.apply(StatusKind::Haste, 4.0);
+        statuses.apply(StatusKind::Haste, 4.0);
+        assert_eq!(statuses.effects[&StatusKind::Haste].remaining, 8.0);
+
+        statuses.apply(StatusKind::Slow, 4.0);
+        statuses.apply(StatusKind::Slow, 2.0);
+        assert_eq!(statuses.effects[&StatusKind::Slow].remaining, 4.0);
+        assert_eq!(statuses.effects[&StatusKind::Slow].stacks, 1);
+
+        for _ in 0..10 {
+            statuses.apply(StatusKind::Poison, 3.0);
+        }
+        assert_eq!(statuses.effects[&StatusKind::Poison].stacks, 5);
+        assert_eq!(statuses.modifiers().speed, 5);
+    }
+
+    #[test]
+    fn poison_ticks_and_expires() {
+        let mut world = World::default();
+        let victim = world.spawn();
+        world.healths.insert(victim, Health::new(20));
+        world.energies.insert(victim, Energy::new(10));
+
+        world.apply_status(victim, StatusKind::Poison);
+        world.apply_status(victim, StatusKind::Poison);
+        world.apply_status(victim, StatusKind::Slow);
+        assert_eq!(world.speed(victim), 5);
+
+        for _ in 0..5 {
+            world.update_statuses(DeltaTime(0.5));
+        }
+        assert_eq!(world.healths[&victim].current, 16);
+
+        for _ in 0..3 {
+            world.update_statuses(DeltaTime(0.5));
+        }
+        assert!(!world.statuses[&victim].effects.contains_key(&StatusKind::Slow));
+
+        for _ in 0..5 {
+            world.update_statuses(DeltaTime(0.5));
+        }
+        assert_eq!(world.healths[&victim].current, 10);
+        assert!(!world.statuses.contains_key(&victim));
+        assert_eq!(world.speed(victim), 10);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::ai::AiState;
+use crate::ecs::EntityId;
+use crate::light::LIGHT_RADIUS;
+use crate::pathfinding::CARDINAL_COST;
+use crate::pathfinding::CornerCutting;
+use crate::pathfinding::DijkstraMap;
+use crate::pathfinding::PathOptions;
+use crate::world::World;
+
+/// The awareness at which a monster has definitely noticed the player
+pub const FULL_AWARENESS: i32 = 100;
+/// Awareness lost every turn the player is out of sight
+pub const AWARENESS_DECAY: i32 = 5;
+
+/// The light level everywhere, even far from any light
+const AMBIENT_LIGHT: f32 = 0.1;
+/// The chance of being noticed each turn in plain sight, even in total darkness
+const MIN_DETECTION_CHANCE: f32 = 0.05;
+
+impl World {
+    /// Get how brightly lit a tile is, from 0 for pitch black to 1 for fully lit
+    ///
+    /// Both the level's own lights and those carried around count, but only if they have a clear
+    /// line of sight to the tile, so walls cast shadows.
+    pub fn light_level(&self, position: [i32; 2]) -> f32 {
+        let lit: f32 = self.lights
+            .iter()
+            .chain(self.light_sources.values())
+            .filter_map(|light| {
+                let [x, y] = light.position();
+                let distance = ((x - position[0] as f32).powi(2) + (y - position[1] as f32).powi(2)).sqrt();
+                let origin = [x.round() as i32, y.round() as i32];
+                self.can_see(origin, position, LIGHT_RADIUS).then(|| light.brightness(distance))
+            })
+            .sum();
+
+        (AMBIENT_LIGHT + lit).min(1.0)
+    }
+
+    /// Get the chance that something in plain sight at a position is noticed each turn
+    pub fn detection_chance(&self, position: [i32; 2]) -> f32 {
+        self.light_level(position).max(MIN_DETECTION_CHANCE)
+    }
+
+    /// Spread a noise out from a position, drawing every monster that hears it towards it
+    ///
+    /// Sound travels `loudness` tiles across open floor and is muffled by doors and walls. The
+    /// louder it is where a monster stands, the more aware of the player it becomes.
+    pub fn make_noise(&mut self, source: [i32; 2], loudness: i32) {
+        if loudness <= 0 {
+            return;
+        }
+
+        let range = loudness * CARDINAL_COST as i32;
+        // Only the void stops sound, and it still slips diagonally past a corner of it
+        let options = PathOptions {
+            corner_cutting: CornerCutting::IfOneOpen,
+            search_limit: ((loudness * 2 + 1) * (loudness * 2 + 1)) as usize,
+        };
+        let sound = DijkstraMap::new(&[source], &options, |p| self.tile(p).and_then(|tile| tile.muffling()));
+
+        // Only those out to get the player come looking for them
+        let listeners: Vec<EntityId> = self.ais.keys().filter(|id| self.is_hostile(**id, self.player)).copied().collect();
+        for id in listeners {
+            let volume = match self.positions.get(&id).and_then(|p| sound.distance(*p)) {
+                Some(distance) if distance < range => range - distance,
+                _ => continue,
+            };
+
+            let ai = match self.ais.get_mut(&id) {
+                Some(ai) => ai,
+                None => continue,
+            };
+
+            ai.awareness = (ai.awareness + volume).min(FULL_AWARENESS);
+            if !matches!(ai.state, AiState::Attack | AiState::Flee) {
+                ai.state = AiState::Chase { last_seen: source };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai::Ai;
+    use crate::ai::AiState;
+    use crate::ecs::EntityId;
+    use crate::light::Light;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    /// An open chunk split by a wall down column 8, with a closed door in it at row 4
+    fn world() -> World {
+        let mut chunk = [[Tile::Planks as u8; 16]; 16];
+        for row in chunk.iter_mut() {
+            row[8] = Tile::Wall as u8;
+        }
+        chunk[4][8] = Tile::DoorClosed as u8;
+
+        World::test(chunk, [15, 15])
+    }
+
+    fn listener(world: &mut World, position: [i32; 2]) -> EntityId {
+        let id = world.spawn();
+        world.positions.insert(id, position);
+        world.ais.insert(id, Ai::new(position));
+        id
+    }
+
+    #[test]
+    fn noise_is_muffled() {
+        let mut world = world();
+        let near = listener(&mut world, [5, 4]);
+        let behind_door = listener(&mut world, [10, 4]);
+        let behind_wall = listener(&mut world, [10, 12]);
+
+        world.make_noise([6, 4], 8);
+        assert_eq!(world.ais[&near].state, AiState::Chase { last_seen: [6, 4] });
+        assert_eq!(world.ais[&near].awareness, 70);
+        assert!(world.ais[&behind_door].awareness > 0);
+        assert_eq!(world.ais[&behind_wall].state, AiState::Wander);
+
+        world.make_noise([6, 4], 20);
+        assert_eq!(world.ais[&behind_wall].state, AiState::Chase { last_seen: [6, 4] });
+    }
+
+    #[test]
+    fn noise_slips_past_corners() {
+        let mut chunk = [[Tile::Planks as u8; 16]; 16];
+        chunk[0][1] = Tile::Void as u8;
+        let mut world = World::test(chunk, [15, 15]);
+        let listener = listener(&mut world, [1, 1]);
+
+        world.make_noise([0, 0], 2);
+        assert_eq!(world.ais[&listener].awareness, 6);
+    }
+
+    #[test]
+    fn light_reveals() {
+        let mut world = world();
+        assert_eq!(world.light_level([3, 3]), 0.1);
+
+        let torch = world.spawn();
+        world.light_sources.insert(torch, Light::new([3.0, 3.0], [255, 255, 255], 255));
+        assert_eq!(world.light_level([3, 3]), 1.0);
+        assert!(world.light_level([7, 3]) < world.light_level([5, 3]));
+        assert_eq!(world.detection_chance([15, 15]), 0.1);
+    }
+
+    #[test]
+    fn walls_cast_shadows() {
+        let mut world = world();
+        let torch = world.spawn();
+        world.light_sources.insert(torch, Light::new([6.0, 8.0], [255, 255, 255], 255));
+        assert!(world.light_level([4, 8]) > 0.1);
+        assert_eq!(world.light_level([10, 8]), 0.1);
+    }
+
+    #[test]
+    fn level_lights_count() {
+        let mut world = world();
+        world.lights.push(Light::new([12.0, 12.0], [255, 255, 255], 255));
+        assert_eq!(world.light_level([12, 12]), 1.0);
+        assert_eq!(world.light_level([4, 12]), 0.1);
+    }
+}
@@ -0,0 +1,213 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::chunk::split_position;
+use crate::combat::DamageSource;
+use crate::ecs::EntityId;
+use crate::entity::Entity;
+use crate::item::ItemKind;
+use crate::time::DeltaTime;
+use crate::world::World;
+
+/// The damage a single dig does to a tile
+pub const DIG_DAMAGE: u32 = 10;
+/// How many tiles of open floor an explosion can be heard across
+pub const EXPLOSION_LOUDNESS: i32 = 20;
+/// How long a bomb takes to go off once it's lit, in seconds
+pub const BOMB_FUSE: f32 = 3.0;
+
+/// A lit bomb waiting to go off
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Fuse {
+    pub owner: EntityId,
+    pub position: [i32; 2],
+    /// Seconds until it explodes
+    pub remaining: f32,
+    pub radius: i32,
+    pub damage: u32,
+}
+
+impl World {
+    /// Damage the tile at a position, returning whether it was destroyed
+    ///
+    /// Tiles without a durability can't be damaged. Destroyed tiles are replaced by whatever the
+    /// tile leaves behind.
+    pub fn damage_tile(&mut self, position: [i32; 2], damage: u32) -> bool {
+        let (tile, durability) = match self.tile(position).and_then(|tile| Some((tile, tile.durability()?))) {
+            Some(tile) => tile,
+            None => return false,
+        };
+
+        let (chunk_position, [x, y]) = split_position(position);
+        let index = match self.chunk_index(chunk_position) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        let chunk_damage = self.tile_damage.entry(index).or_default();
+        chunk_damage[y][x] += damage;
+        if chunk_damage[y][x] < durability {
+            return false;
+        }
+
+        chunk_damage[y][x] = 0;
+        if chunk_damage.iter().flatten().all(|damage| *damage == 0) {
+            self.tile_damage.remove(&index);
+        }
+
+        self.set_tile(position, tile.destroyed())
+    }
+
+    /// Dig into an adjacent tile, returning whether it could be dug at all
+    pub fn dig(&mut self, actor: EntityId, position: [i32; 2]) -> bool {
+        let from = match self.positions.get(&actor) {
+            Some(from) => *from,
+            None => return false,
+        };
+
+        if (from[0] - position[0]).abs() > 1 || (from[1] - position[1]).abs() > 1 {
+            return false;
+        }
+
+        if self.tile(position).and_then(|tile| tile.durability()).is_none() {
+            return false;
+        }
+
+        self.damage_tile(position, DIG_DAMAGE);
+        true
+    }
+
+    /// Damage every tile and entity within a radius of a position, blaming `source` for it
+    pub fn explode(&mut self, center: [i32; 2], radius: i32, damage: u32, source: Option<EntityId>) {
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                if x * x + y * y <= radius * radius {
+                    self.damage_tile([center[0] + x, center[1] + y], damage);
+                }
+            }
+        }
+
+        let victims: Vec<EntityId> = self.positions
+            .iter()
+            .filter(|(id, p)| {
+                let [dx, dy] = [p[0] - center[0], p[1] - center[1]];
+                self.healths.contains_key(id) && dx * dx + dy * dy <= radius * radius
+            })
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in victims {
+            self.damage(id, damage as i32, DamageSource::Explosion(source));
+        }
+
+        self.make_noise(center, EXPLOSION_LOUDNESS);
+    }
+
+    /// Light a bomb at an entity's feet, returning whether there was anywhere to put it
+    pub fn place_bomb(&mut self, owner: EntityId, radius: i32, damage: u32) -> bool {
+        let position = match self.positions.get(&owner) {
+            Some(position) => *position,
+            None => return false,
+        };
+
+        let id = self.spawn();
+        let sprite_position = [position[0] as f32, position[1] as f32];
+        let (atlas_position, color) = (ItemKind::Bomb.atlas_position(), ItemKind::Bomb.color());
+        self.entities.insert(id, Entity::new(sprite_position, atlas_position, [1, 1], color, None));
+        self.fuses.insert(id, Fuse { owner, position, remaining: BOMB_FUSE, radius, damage });
+        true
+    }
+
+    /// Burn down every lit fuse, setting off the bombs that run out
+    pub fn update_fuses(&mut self, dt: DeltaTime) {
+        let ids: Vec<EntityId> = self.fuses.keys().copied().collect();
+        for id in ids {
+            let fuse = self.fuses.get_mut(&id).unwrap();
+            fuse.remaining -= dt.0;
+            if fuse.remaining > 0.0 {
+                continue;
+            }
+
+            let fuse = self.fuses.remove(&id).unwrap();
+            self.despawn(id);
+            self.explode(fuse.position, fuse.radius, fuse.damage, Some(fuse.owner));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::health::Health;
+    use crate::pathfinding::PathOptions;
+    use crate::pathfinding::find_path;
+    use crate::tile::Tile;
+    use crate::time::DeltaTime;
+    use crate::world::World;
+
+    use super::BOMB_FUSE;
+
+    fn world() -> World {
+        let mut chunk = [[Tile::Wall as u8; 16]; 16];
+        chunk[1] = [Tile::Planks as u8; 16];
+        chunk[3] = [Tile::Planks as u8; 16];
+
+        let mut world = World::test(chunk, [5, 1]);
+        let player = world.player;
+        world.healths.insert(player, Health::new(10));
+        world
+    }
+
+    #[test]
+    fn digging_opens_new_paths() {
+        let mut world = world();
+        let player = world.player;
+        let options = PathOptions::default();
+        assert_eq!(find_path([5, 1], [5, 3], &options, |p| world.movement_cost(p)), None);
+
+        assert!(world.dig(player, [5, 2]));
+        assert_eq!(world.tile_damage[&0][2][5], 10);
+        assert!(world.dig(player, [5, 2]));
+        assert!(world.dig(player, [5, 2]));
+        assert_eq!(world.tile([5, 2]), Some(Tile::Rubble));
+        assert!(world.tile_damage.is_empty());
+        assert!(world.dirty_chunks.contains(&0));
+
+        assert_eq!(find_path([5, 1], [5, 3], &options, |p| world.movement_cost(p)).map(|path| path.len()), Some(2));
+        assert!(!world.dig(player, [5, 2]));
+        assert!(!world.dig(player, [5, 5]));
+    }
+
+    #[test]
+    fn explosions_break_walls_and_hurt() {
+        let mut world = world();
+        let player = world.player;
+        let bystander = world.spawn();
+        world.positions.insert(bystander, [6, 1]);
+        world.healths.insert(bystander, Health::new(50));
+
+        world.move_entity(player, [8, 1]);
+        world.explode([5, 1], 1, 30, Some(player));
+        assert_eq!(world.tile([5, 0]), Some(Tile::Rubble));
+        assert_eq!(world.tile([5, 2]), Some(Tile::Rubble));
+        assert_eq!(world.tile([6, 2]), Some(Tile::Wall));
+        assert_eq!(world.healths[&player].current, 10);
+        assert_eq!(world.healths[&bystander].current, 20);
+    }
+
+    #[test]
+    fn bombs_wait_for_their_fuse() {
+        let mut world = world();
+        let player = world.player;
+        world.healths.insert(player, Health::new(50));
+        assert!(world.place_bomb(player, 1, 30));
+
+        world.update_fuses(DeltaTime(BOMB_FUSE - 1.0));
+        assert_eq!(world.healths[&player].current, 50);
+
+        // Whoever lit it gets caught in the blast if they don't move away in time
+        world.update_fuses(DeltaTime(1.0));
+        assert!(world.fuses.is_empty());
+        assert_eq!(world.healths[&player].current, 20);
+        assert_eq!(world.tile([5, 0]), Some(Tile::Rubble));
+    }
+}
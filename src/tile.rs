@@ -19,11 +19,20 @@ pub struct TileData {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug, Deserialize, FromPrimitive, Serialize, ToPrimitive)]
+#[derive(Copy, Clone, Debug, Deserialize, Eq, FromPrimitive, PartialEq, Serialize, ToPrimitive)]
 pub enum Tile {
     Void = 0,
     Wall,
     Planks,
+    DoorClosed,
+    DoorOpen,
+    LeverOff,
+    LeverOn,
+    Chest,
+    ChestOpen,
+    Rubble,
+    /// Looks and acts like a wall until it's found, when it becomes a door
+    SecretDoor,
 }
 
 impl Tile {
@@ -33,6 +42,14 @@ impl Tile {
             Tile::Void => Material::Void,
             Tile::Wall => Material::Wall,
             Tile::Planks => Material::OrderlyTwist,
+            Tile::DoorClosed => Material::Solid,
+            Tile::DoorOpen => Material::UncutTile,
+            Tile::LeverOff => Material::Wall,
+            Tile::LeverOn => Material::Wall,
+            Tile::Chest => Material::Solid,
+            Tile::ChestOpen => Material::UncutTile,
+            Tile::Rubble => Material::UncutTile,
+            Tile::SecretDoor => Material::Wall,
         }
     }
 
@@ -42,6 +59,14 @@ impl Tile {
             Tile::Void => [0, 0, 0, 0],
             Tile::Wall => [255, 255, 255, 255],
             Tile::Planks => [255, 255, 255, 255],
+            Tile::DoorClosed => [120, 72, 32, 255],
+            Tile::DoorOpen => [120, 72, 32, 255],
+            Tile::LeverOff => [255, 255, 255, 255],
+            Tile::LeverOn => [255, 255, 255, 255],
+            Tile::Chest => [160, 120, 40, 255],
+            Tile::ChestOpen => [160, 120, 40, 255],
+            Tile::Rubble => [128, 128, 128, 255],
+            Tile::SecretDoor => [255, 255, 255, 255],
         }
     }
 
@@ -50,10 +75,122 @@ impl Tile {
         match self {
             Tile::Wall => [0, 0, 255, 255],
             Tile::Planks => [220, 220, 220, 255],
+            Tile::DoorOpen => [60, 36, 16, 255],
+            Tile::LeverOff => [200, 0, 0, 255],
+            Tile::LeverOn => [0, 200, 0, 255],
+            Tile::ChestOpen => [40, 30, 10, 255],
+            Tile::Rubble => [80, 80, 80, 255],
+            Tile::SecretDoor => [0, 0, 255, 255],
             _ => [0, 0, 0, 0],
         }
     }
 
+    /// Whether a specific tile blocks movement
+    pub fn is_solid(self) -> bool {
+        match self {
+            Tile::Void => true,
+            Tile::Wall => true,
+            Tile::Planks => false,
+            Tile::DoorClosed => true,
+            Tile::DoorOpen => false,
+            Tile::LeverOff => true,
+            Tile::LeverOn => true,
+            Tile::Chest => true,
+            Tile::ChestOpen => false,
+            Tile::Rubble => false,
+            Tile::SecretDoor => true,
+        }
+    }
+
+    /// Get the relative cost of walking across a specific tile
+    pub fn movement_cost(self) -> u32 {
+        match self {
+            Tile::Void => 1,
+            Tile::Wall => 1,
+            Tile::Planks => 1,
+            Tile::DoorClosed => 1,
+            Tile::DoorOpen => 1,
+            Tile::LeverOff => 1,
+            Tile::LeverOn => 1,
+            Tile::Chest => 1,
+            Tile::ChestOpen => 1,
+            Tile::Rubble => 2,
+            Tile::SecretDoor => 1,
+        }
+    }
+
+    /// Whether a specific tile blocks line of sight
+    pub fn is_opaque(self) -> bool {
+        match self {
+            Tile::Void => false,
+            Tile::Wall => true,
+            Tile::Planks => false,
+            Tile::DoorClosed => true,
+            Tile::DoorOpen => false,
+            Tile::LeverOff => true,
+            Tile::LeverOn => true,
+            Tile::Chest => false,
+            Tile::ChestOpen => false,
+            Tile::Rubble => false,
+            Tile::SecretDoor => true,
+        }
+    }
+
+    /// Get how many tiles of open floor a specific tile muffles sound as much as, if sound passes at all
+    pub fn muffling(self) -> Option<u32> {
+        match self {
+            Tile::Void => None,
+            Tile::Wall => Some(8),
+            Tile::Planks => Some(1),
+            Tile::DoorClosed => Some(4),
+            Tile::DoorOpen => Some(1),
+            Tile::LeverOff => Some(8),
+            Tile::LeverOn => Some(8),
+            Tile::Chest => Some(1),
+            Tile::ChestOpen => Some(1),
+            Tile::Rubble => Some(1),
+            Tile::SecretDoor => Some(8),
+        }
+    }
+
+    /// Get the damage a specific tile can take before it's destroyed, if it can be destroyed at all
+    pub fn durability(self) -> Option<u32> {
+        match self {
+            Tile::Wall => Some(30),
+            Tile::DoorClosed => Some(20),
+            Tile::DoorOpen => Some(20),
+            Tile::LeverOff => Some(20),
+            Tile::LeverOn => Some(20),
+            Tile::Chest => Some(10),
+            Tile::ChestOpen => Some(10),
+            Tile::SecretDoor => Some(30),
+            _ => None,
+        }
+    }
+
+    /// Get the tile left behind when a specific tile is destroyed
+    pub fn destroyed(self) -> Tile {
+        match self {
+            Tile::Chest => Tile::Planks,
+            Tile::ChestOpen => Tile::Planks,
+            _ => Tile::Rubble,
+        }
+    }
+
+    /// Get what a specific tile becomes when interacted with, if it can be interacted with at all
+    ///
+    /// Secret doors can't be interacted with until they're found.
+    pub fn toggled(self) -> Option<Tile> {
+        match self {
+            Tile::DoorClosed => Some(Tile::DoorOpen),
+            Tile::DoorOpen => Some(Tile::DoorClosed),
+            Tile::LeverOff => Some(Tile::LeverOn),
+            Tile::LeverOn => Some(Tile::LeverOff),
+            Tile::Chest => Some(Tile::ChestOpen),
+            _ => None,
+        }
+    }
+
     pub fn tile_data() -> Vec<TileData> {
         let mut atlas = vec![];
         for i in 0..=255 {
@@ -29,3 +29,37 @@ impl Time {
         dt
     }
 }
+
+/// Converts variable frame times into a whole number of fixed length ticks
+pub struct FixedTick {
+    period: f32,
+    accumulator: f32,
+}
+
+impl FixedTick {
+    pub fn new(period: f32) -> Self {
+        Self { period, accumulator: 0.0 }
+    }
+
+    /// Accumulate a frame's worth of time and return the number of ticks that have elapsed
+    pub fn advance(&mut self, dt: DeltaTime) -> u32 {
+        self.accumulator += dt.0;
+        let ticks = (self.accumulator / self.period) as u32;
+        self.accumulator -= ticks as f32 * self.period;
+        ticks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeltaTime;
+    use super::FixedTick;
+
+    #[test]
+    fn fixed_tick_carries_remainder() {
+        let mut tick = FixedTick::new(0.1);
+        assert_eq!(tick.advance(DeltaTime(0.25)), 2);
+        assert_eq!(tick.advance(DeltaTime(0.06)), 1);
+        assert_eq!(tick.advance(DeltaTime(0.0)), 0);
+    }
+}
@@ -0,0 +1,328 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat::DamageSource;
+use crate::ecs::EntityId;
+use crate::entity::Entity;
+use crate::log::Event;
+use crate::rng::Stream;
+use crate::tile::Tile;
+use crate::world::World;
+
+/// How far away the player notices hidden things without looking for them
+pub const PASSIVE_SEARCH_RADIUS: i32 = 1;
+/// The chance in percent of noticing something hidden in passing, before perception
+pub const PASSIVE_SEARCH_CHANCE: i32 = 10;
+pub const SEARCH_RADIUS: i32 = 2;
+/// The chance in percent of finding something hidden by searching, before perception
+pub const SEARCH_CHANCE: i32 = 50;
+
+const SPIKE_DAMAGE: i32 = 5;
+const TELEPORT_RADIUS: i32 = 8;
+const ALARM_LOUDNESS: i32 = 20;
+/// The number of places tried before giving up on placing something
+const PLACEMENT_ATTEMPTS: u32 = 100;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum TrapKind {
+    /// Stabs whoever steps on it
+    Spike,
+    /// Sends whoever steps on it somewhere else nearby
+    Teleport,
+    /// Alerts every monster in earshot to where it went off
+    Alarm,
+}
+
+impl TrapKind {
+    pub const ALL: [TrapKind; 3] = [TrapKind::Spike, TrapKind::Teleport, TrapKind::Alarm];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TrapKind::Spike => "spike trap",
+            TrapKind::Teleport => "teleport trap",
+            TrapKind::Alarm => "alarm trap",
+        }
+    }
+
+    /// Get the position of a specific trap kind's sprite in the entity atlas
+    pub fn atlas_position(self) -> [u32; 2] {
+        match self {
+            TrapKind::Spike => [0, 2],
+            TrapKind::Teleport => [1, 2],
+            TrapKind::Alarm => [2, 2],
+        }
+    }
+
+    /// Get the color of a specific trap kind's sprite
+    pub fn color(self) -> u32 {
+        match self {
+            TrapKind::Spike => 0xffc0_c0c0,
+            TrapKind::Teleport => 0xffff_40c0,
+            TrapKind::Alarm => 0xff20_c0ff,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Trap {
+    pub kind: TrapKind,
+    /// Hidden traps aren't drawn until they're found or set off
+    pub hidden: bool,
+}
+
+impl World {
+    /// Place a trap at a position
+    pub fn spawn_trap(&mut self, kind: TrapKind, position: [i32; 2], hidden: bool) -> EntityId {
+        let id = self.spawn();
+        self.positions.insert(id, position);
+        self.traps.insert(id, Trap { kind, hidden: true });
+        if !hidden {
+            self.reveal_trap(id);
+        }
+
+        id
+    }
+
+    /// Find the trap at a position
+    pub fn trap_at(&self, position: [i32; 2]) -> Option<EntityId> {
+        self.traps.keys().find(|id| self.positions.get(id) == Some(&position)).copied()
+    }
+
+    /// Make a trap visible, giving it a sprite
+    fn reveal_trap(&mut self, id: EntityId) {
+        let (trap, position) = match (self.traps.get_mut(&id), self.positions.get(&id)) {
+            (Some(trap), Some(position)) => (trap, *position),
+            _ => return,
+        };
+
+        trap.hidden = false;
+        let sprite_position = [position[0] as f32, position[1] as f32];
+        let entity = Entity::new(sprite_position, trap.kind.atlas_position(), [1, 1], trap.kind.color(), None);
+        self.entities.insert(id, entity);
+    }
+
+    /// Set off whatever trap lies under an entity, returning whether there was one
+    pub fn trigger_trap(&mut self, actor: EntityId) -> bool {
+        let position = match self.positions.get(&actor) {
+            Some(position) => *position,
+            None => return false,
+        };
+
+        let (id, kind) = match self.trap_at(position) {
+            Some(id) => (id, self.traps[&id].kind),
+            None => return false,
+        };
+
+        if actor == self.player || self.visibility.is_visible(position) {
+            self.reveal_trap(id);
+        }
+
+        self.notify(Event::TrapTriggered(actor, kind));
+        match kind {
+            TrapKind::Spike => self.damage(actor, SPIKE_DAMAGE, DamageSource::Trap(kind)),
+            TrapKind::Teleport => {
+                self.teleport(actor, TELEPORT_RADIUS);
+            }
+            TrapKind::Alarm => self.make_noise(position, ALARM_LOUDNESS),
+        }
+
+        true
+    }
+
+    /// Move an entity to a random passable tile within `radius`, returning whether it found one
+    pub fn teleport(&mut self, actor: EntityId, radius: i32) -> bool {
+        let position = match self.positions.get(&actor) {
+            Some(position) => *position,
+            None => return false,
+        };
+
+        let rng = self.rng(Stream::Traps);
+        let destinations: Vec<[i32; 2]> = (0..PLACEMENT_ATTEMPTS)
+            .map(|_| {
+                let x = rng.range(-radius..radius + 1);
+                let y = rng.range(-radius..radius + 1);
+                [position[0] + x, position[1] + y]
+            })
+            .collect();
+
+        match destinations.into_iter().find(|p| self.is_passable(*p)) {
+            Some(destination) => {
+                self.move_entity(actor, destination);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Look for hidden traps and secret doors around an entity, returning how many were found
+    ///
+    /// Each hidden thing within `radius` is found with `chance` percent, plus the entity's perception.
+    pub fn search(&mut self, actor: EntityId, radius: i32, chance: i32) -> u32 {
+        let position = match self.positions.get(&actor) {
+            Some(position) => *position,
+            None => return 0,
+        };
+
+        let chance = chance + self.modifiers(actor).perception;
+        let in_range = |p: [i32; 2]| (p[0] - position[0]).abs().max((p[1] - position[1]).abs()) <= radius;
+
+        let traps: Vec<EntityId> = self.traps
+            .iter()
+            .filter(|(id, trap)| trap.hidden && self.positions.get(id).is_some_and(|p| in_range(*p)))
+            .map(|(id, _)| *id)
+            .collect();
+
+        let doors: Vec<[i32; 2]> = (-radius..=radius)
+            .flat_map(|y| (-radius..=radius).map(move |x| [position[0] + x, position[1] + y]))
+            .filter(|p| self.tile(*p) == Some(Tile::SecretDoor))
+            .collect();
+
+        let mut found = 0;
+        for id in traps {
+            if self.rng(Stream::Traps).range(0..100) < chance {
+                self.reveal_trap(id);
+                self.notify(Event::TrapFound(actor, self.traps[&id].kind));
+                found += 1;
+            }
+        }
+
+        for door in doors {
+            if self.rng(Stream::Traps).range(0..100) < chance {
+                self.set_tile(door, Tile::DoorClosed);
+                self.notify(Event::SecretDoorFound(actor));
+                found += 1;
+            }
+        }
+
+        found
+    }
+
+    /// Scatter hidden traps over the floor and turn walls between two open tiles into secret doors
+    ///
+    /// Placement only draws from the map generation stream, so the same seed always hides the
+    /// same things in the same places.
+    pub fn place_hidden_features(&mut self, traps: u32, secret_doors: u32) {
+        let [width, height] = self.size();
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let mut placed = 0;
+        for _ in 0..traps * PLACEMENT_ATTEMPTS {
+            if placed == traps {
+                break;
+            }
+
+            let rng = self.rng(Stream::Mapgen);
+            let position = [rng.range(0..width), rng.range(0..height)];
+            let kind = TrapKind::ALL[rng.range(0..TrapKind::ALL.len() as i32) as usize];
+            if self.is_passable(position) && self.trap_at(position).is_none() && self.items_at(position).is_empty() {
+                self.spawn_trap(kind, position, true);
+                placed += 1;
+            }
+        }
+
+        let mut placed = 0;
+        for _ in 0..secret_doors * PLACEMENT_ATTEMPTS {
+            if placed == secret_doors {
+                break;
+            }
+
+            let rng = self.rng(Stream::Mapgen);
+            let [x, y] = [rng.range(0..width), rng.range(0..height)];
+            if self.tile([x, y]) != Some(Tile::Wall) {
+                continue;
+            }
+
+            // Only walls with open floor on opposite sides and wall on the others make sense as doors
+            let open = |p: [i32; 2]| self.tile(p).is_some_and(|tile| !tile.is_solid());
+            let wall = |p: [i32; 2]| self.tile(p) == Some(Tile::Wall);
+            let horizontal = open([x - 1, y]) && open([x + 1, y]) && wall([x, y - 1]) && wall([x, y + 1]);
+            let vertical = open([x, y - 1]) && open([x, y + 1]) && wall([x - 1, y]) && wall([x + 1, y]);
+            if horizontal || vertical {
+                self.set_tile([x, y], Tile::SecretDoor);
+                placed += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai::Ai;
+    use crate::ai::AiState;
+    use crate::chunk::Chunk;
+    use crate::health::Health;
+    use crate::tile::Tile;
+    use crate::world::World;
+
+    use super::TrapKind;
+
+    /// Two open rows separated by a wall along row 2
+    fn chunk() -> Chunk {
+        let mut chunk = [[Tile::Planks as u8; 16]; 16];
+        chunk[2] = [Tile::Wall as u8; 16];
+        chunk
+    }
+
+    #[test]
+    fn traps_trigger_and_reveal() {
+        let mut world = World { seed: 1, ..World::test(chunk(), [5, 1]) };
+        let player = world.player;
+        world.healths.insert(player, Health::new(20));
+        let monster = world.spawn();
+        world.positions.insert(monster, [10, 10]);
+        world.ais.insert(monster, Ai::new([10, 10]));
+
+        let spike = world.spawn_trap(TrapKind::Spike, [6, 1], true);
+        world.spawn_trap(TrapKind::Alarm, [7, 1], true);
+        assert!(world.is_passable([6, 1]));
+        assert!(!world.entities.contains_key(&spike));
+
+        world.move_entity(player, [6, 1]);
+        assert!(world.trigger_trap(player));
+        assert_eq!(world.healths[&player].current, 15);
+        assert!(!world.traps[&spike].hidden);
+        assert!(world.entities.contains_key(&spike));
+
+        world.move_entity(player, [7, 1]);
+        world.trigger_trap(player);
+        assert_eq!(world.ais[&monster].state, AiState::Chase { last_seen: [7, 1] });
+
+        world.spawn_trap(TrapKind::Teleport, [8, 1], false);
+        world.move_entity(player, [8, 1]);
+        world.trigger_trap(player);
+        assert_ne!(world.positions[&player], [8, 1]);
+        assert!(world.is_passable([8, 1]));
+    }
+
+    #[test]
+    fn searching_finds_hidden_things() {
+        let mut world = World { seed: 1, ..World::test(chunk(), [5, 1]) };
+        let player = world.player;
+        let trap = world.spawn_trap(TrapKind::Spike, [6, 0], true);
+        world.set_tile([5, 2], Tile::SecretDoor);
+        assert!(!world.is_passable([5, 2]));
+
+        assert_eq!(world.search(player, 1, 0), 0);
+        assert_eq!(world.search(player, 1, 100), 2);
+        assert!(!world.traps[&trap].hidden);
+        assert_eq!(world.tile([5, 2]), Some(Tile::DoorClosed));
+    }
+
+    #[test]
+    fn placement_is_seeded() {
+        let placed = |seed| {
+            let mut world = World { seed, ..World::test(chunk(), [5, 1]) };
+            world.place_hidden_features(3, 2);
+            let traps: Vec<_> = world.traps.keys().map(|id| world.positions[id]).collect();
+            (traps, world.chunks[0])
+        };
+
+        let (traps, chunk) = placed(4);
+        assert_eq!(traps.len(), 3);
+        assert_eq!(chunk.iter().flatten().filter(|tile| **tile == Tile::SecretDoor as u8).count(), 2);
+        assert_eq!(placed(4), (traps, chunk));
+        assert_ne!(placed(5).0, placed(4).0);
+    }
+}
@@ -1,19 +1,288 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use num_traits::FromPrimitive;
+use num_traits::ToPrimitive;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::ability::Abilities;
+use crate::ability::Targeting;
+use crate::ai::Ai;
 use crate::chunk::Chunk;
+use crate::chunk::CHUNK_SIZE;
+use crate::chunk::ChunkDamage;
+use crate::combat::Attack;
+use crate::combat::Corpse;
+use crate::combat::Defense;
+use crate::dialogue::Conversation;
+use crate::dialogue::Dialogue;
+use crate::chunk::split_position;
+use crate::ecs::Components;
+use crate::ecs::EntityId;
 use crate::entity::Entity;
+use crate::equipment::Equipment;
+use crate::faction::Faction;
+use crate::fov::Visibility;
+use crate::health::Health;
+use crate::interaction::Lever;
+use crate::inventory::Inventory;
+use crate::item::Item;
+use crate::item::ItemKind;
 use crate::light::Light;
+use crate::log::MessageLog;
+use crate::loot::Loot;
+use crate::morgue::RunStats;
+use crate::progression::Progression;
+use crate::projectile::Projectile;
+use crate::projectile::Ranged;
+use crate::rng::Streams;
+use crate::scheduler::Energy;
+use crate::scheduler::SimulationMode;
+use crate::shop::Shop;
+use crate::shop::Trade;
+use crate::status::Statuses;
+use crate::terrain::Fuse;
+use crate::tile::Tile;
+use crate::trap::Trap;
 
 #[derive(Deserialize, Serialize)]
 pub struct World {
     pub name: String,
     pub seed: u32,
+    /// How many levels below the surface the world is
+    pub depth: u32,
+    pub mode: SimulationMode,
+    pub player: EntityId,
+    pub streams: Streams,
+    pub next_entity_id: u32,
+
+    /// What entities are called in messages, such as "goblin"
+    pub names: Components<String>,
+    pub positions: Components<[i32; 2]>,
+    pub energies: Components<Energy>,
+    pub ais: Components<Ai>,
+    pub healths: Components<Health>,
+    pub attacks: Components<Attack>,
+    pub defenses: Components<Defense>,
+    pub corpses: Components<Corpse>,
+    pub ranged: Components<Ranged>,
+    pub projectiles: Components<Projectile>,
+    /// Lit bombs waiting to go off
+    pub fuses: Components<Fuse>,
+    pub items: Components<Item>,
+    pub inventories: Components<Inventory>,
+    pub equipment: Components<Equipment>,
+    /// Lights carried around by entities, such as held torches
+    pub light_sources: Components<Light>,
+    pub statuses: Components<Statuses>,
+    pub loot: Components<Loot>,
+    pub progressions: Components<Progression>,
+    /// Experience granted to the player for killing an entity
+    pub rewards: Components<u32>,
+    pub traps: Components<Trap>,
+    pub factions: Components<Faction>,
+    pub shops: Components<Shop>,
+    pub abilities: Components<Abilities>,
+    /// What NPCs have to say when talked to
+    pub dialogues: Components<Dialogue>,
+    pub levers: Vec<Lever>,
+    /// Damage taken by tiles, keyed by the index of their chunk
+    pub tile_damage: BTreeMap<usize, ChunkDamage>,
+    pub visibility: Visibility,
+    pub log: MessageLog,
+    pub stats: RunStats,
+    /// Named facts about the run set by dialogue, such as quests accepted
+    pub flags: BTreeSet<String>,
+    /// How the player's actions have shifted each faction's attitude towards them
+    pub reputation: BTreeMap<Faction, i32>,
+    /// Item kinds the player has learned to recognise, rather than only knowing what they look like
+    pub identified: BTreeSet<ItemKind>,
+    pub conversation: Option<Conversation>,
+    pub trade: Option<Trade>,
+    pub targeting: Option<Targeting>,
 
+    /// The number of chunks in each row of `chunks`
+    #[serde(skip)]
+    pub width: u32,
     #[serde(skip)]
     pub chunks: Vec<Chunk>,
     #[serde(skip)]
-    pub entities: Vec<Entity>,
+    pub entities: Components<Entity>,
     #[serde(skip)]
     pub lights: Vec<Light>,
+    /// Indices of chunks that have changed since they were last uploaded
+    #[serde(skip)]
+    pub dirty_chunks: BTreeSet<usize>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            seed: Default::default(),
+            depth: Default::default(),
+            mode: Default::default(),
+            player: EntityId(0),
+            streams: Default::default(),
+            // The player always comes first, so nothing spawned afterwards can be mistaken for them
+            next_entity_id: 1,
+            names: Default::default(),
+            positions: Default::default(),
+            energies: Default::default(),
+            ais: Default::default(),
+            healths: Default::default(),
+            attacks: Default::default(),
+            defenses: Default::default(),
+            corpses: Default::default(),
+            ranged: Default::default(),
+            projectiles: Default::default(),
+            fuses: Default::default(),
+            items: Default::default(),
+            inventories: Default::default(),
+            equipment: Default::default(),
+            light_sources: Default::default(),
+            statuses: Default::default(),
+            loot: Default::default(),
+            progressions: Default::default(),
+            rewards: Default::default(),
+            traps: Default::default(),
+            factions: Default::default(),
+            shops: Default::default(),
+            abilities: Default::default(),
+            dialogues: Default::default(),
+            levers: Default::default(),
+            tile_damage: Default::default(),
+            visibility: Default::default(),
+            log: Default::default(),
+            stats: Default::default(),
+            flags: Default::default(),
+            reputation: Default::default(),
+            identified: Default::default(),
+            conversation: Default::default(),
+            trade: Default::default(),
+            targeting: Default::default(),
+            width: Default::default(),
+            chunks: Default::default(),
+            entities: Default::default(),
+            lights: Default::default(),
+            dirty_chunks: Default::default(),
+        }
+    }
+}
+
+impl World {
+    /// Reserve an id for a new entity
+    pub fn spawn(&mut self) -> EntityId {
+        let id = EntityId(self.next_entity_id);
+        self.next_entity_id += 1;
+        id
+    }
+
+    /// Remove every component belonging to an entity
+    pub fn despawn(&mut self, id: EntityId) {
+        self.names.remove(&id);
+        self.positions.remove(&id);
+        self.energies.remove(&id);
+        self.ais.remove(&id);
+        self.healths.remove(&id);
+        self.attacks.remove(&id);
+        self.defenses.remove(&id);
+        self.corpses.remove(&id);
+        self.ranged.remove(&id);
+        self.projectiles.remove(&id);
+        self.fuses.remove(&id);
+        self.items.remove(&id);
+        self.inventories.remove(&id);
+        self.equipment.remove(&id);
+        self.light_sources.remove(&id);
+        self.statuses.remove(&id);
+        self.loot.remove(&id);
+        self.progressions.remove(&id);
+        self.rewards.remove(&id);
+        self.traps.remove(&id);
+        self.dialogues.remove(&id);
+        self.factions.remove(&id);
+        self.shops.remove(&id);
+        self.abilities.remove(&id);
+        self.entities.remove(&id);
+    }
+
+    /// Get the index into `chunks` of the chunk at a chunk position
+    pub fn chunk_index(&self, chunk_position: [i32; 2]) -> Option<usize> {
+        let [x, y] = chunk_position;
+        if x < 0 || y < 0 || x as u32 >= self.width {
+            return None;
+        }
+
+        let index = y as usize * self.width as usize + x as usize;
+        (index < self.chunks.len()).then_some(index)
+    }
+
+    /// Get the size of the world in tiles
+    pub fn size(&self) -> [i32; 2] {
+        let rows = self.chunks.len() as u32 / self.width.max(1);
+        [(self.width * CHUNK_SIZE) as i32, (rows * CHUNK_SIZE) as i32]
+    }
+
+    /// Get the tile at a position, or `None` if it lies outside of the world
+    pub fn tile(&self, position: [i32; 2]) -> Option<Tile> {
+        let (chunk_position, [x, y]) = split_position(position);
+        let chunk = &self.chunks[self.chunk_index(chunk_position)?];
+        FromPrimitive::from_u8(chunk[y][x])
+    }
+
+    /// Change the tile at a position, returning whether it lies inside the world
+    ///
+    /// The chunk is marked for upload and anything that depends on the tile is invalidated.
+    pub fn set_tile(&mut self, position: [i32; 2], tile: Tile) -> bool {
+        let (chunk_position, [x, y]) = split_position(position);
+        let index = match self.chunk_index(chunk_position) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        self.chunks[index][y][x] = ToPrimitive::to_u8(&tile).unwrap();
+        self.dirty_chunks.insert(index);
+        self.visibility.invalidate();
+        true
+    }
+
+    /// Whether an entity could stand at a position
+    pub fn is_passable(&self, position: [i32; 2]) -> bool {
+        match self.tile(position) {
+            Some(tile) => !tile.is_solid() && self.entity_at(position).is_none(),
+            None => false,
+        }
+    }
+
+    /// Find the entity standing at a position, ignoring items and traps lying on the ground
+    pub fn entity_at(&self, position: [i32; 2]) -> Option<EntityId> {
+        self.positions
+            .iter()
+            .find(|(id, p)| **p == position && !self.items.contains_key(id) && !self.traps.contains_key(id))
+            .map(|(id, _)| *id)
+    }
+
+    /// Move an entity to a new position, keeping its sprite and light in sync
+    pub fn move_entity(&mut self, id: EntityId, position: [i32; 2]) {
+        self.positions.insert(id, position);
+        if let Some(entity) = self.entities.get_mut(&id) {
+            entity.set_position([position[0] as f32, position[1] as f32]);
+        }
+
+        if let Some(light) = self.light_sources.get_mut(&id) {
+            light.set_position([position[0] as f32, position[1] as f32]);
+        }
+    }
+}
+
+#[cfg(test)]
+impl World {
+    /// Build a world out of a single chunk with the player standing at a position, for tests to start from
+    pub fn test(chunk: Chunk, player: [i32; 2]) -> Self {
+        let mut world = World { width: 1, chunks: vec![chunk], ..Default::default() };
+        world.positions.insert(world.player, player);
+        world
+    }
 }